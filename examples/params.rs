@@ -30,7 +30,7 @@
 //! ## Function Name Logging
 //! To enable function name logging, build with:
 //! ```bash
-//! cargo run --example params --features function-names-pascal
+//! cargo run --example params --features function-names
 //! ```
 
 use log_args::params;
@@ -0,0 +1,166 @@
+//! Text encoding of a context map as `key=value` entries joined by `,`.
+//!
+//! Entries are sorted by key so the same context always produces the same string.
+//! Inside keys and values a backslash escapes the characters that would otherwise be
+//! ambiguous:
+//!
+//! | Character       | Encoded as |
+//! |-----------------|------------|
+//! | `\`             | `\\`       |
+//! | `,`             | `\,`       |
+//! | `=`             | `\=`       |
+//! | `"`             | `\"`       |
+//! | newline         | `\n`       |
+//! | carriage return | `\r`       |
+//! | tab             | `\t`       |
+//!
+//! An empty context is written as `<no_context>`.
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Placeholder for an empty context
+pub const NO_CONTEXT: &str = "<no_context>";
+
+/// Encode `context` as a sorted, escaped `key=value,...` string
+pub fn format_context_string(context: &HashMap<String, String>) -> String {
+    let mut entries: Vec<(&String, &String)> = context.iter().collect();
+    entries.sort();
+    if entries.is_empty() {
+        return NO_CONTEXT.to_string();
+    }
+
+    let mut encoded = String::new();
+    for (index, (key, value)) in entries.into_iter().enumerate() {
+        if index > 0 {
+            encoded.push(',');
+        }
+        escape_into(&mut encoded, key);
+        encoded.push('=');
+        escape_into(&mut encoded, value);
+    }
+    encoded
+}
+
+/// Decode a string produced by [`format_context_string`] back into its entries,
+/// in the order they appear
+pub fn parse_context_string(encoded: &str) -> Result<Vec<(String, String)>, ContextStringError> {
+    let mut entries = Vec::new();
+    if encoded.is_empty() || encoded == NO_CONTEXT {
+        return Ok(entries);
+    }
+
+    let mut key = String::new();
+    let mut value = String::new();
+    let mut in_value = false;
+    let mut chars = encoded.char_indices();
+
+    while let Some((position, ch)) = chars.next() {
+        let current = if in_value { &mut value } else { &mut key };
+        match ch {
+            '\\' => {
+                let (_, escaped) = chars.next().ok_or(ContextStringError {
+                    position,
+                    kind: ErrorKind::TrailingBackslash,
+                })?;
+                current.push(match escaped {
+                    '\\' | ',' | '=' | '"' => escaped,
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    other => {
+                        return Err(ContextStringError {
+                            position,
+                            kind: ErrorKind::UnknownEscape(other),
+                        })
+                    }
+                });
+            }
+            '=' if !in_value => in_value = true,
+            '=' => {
+                return Err(ContextStringError {
+                    position,
+                    kind: ErrorKind::UnescapedEquals,
+                })
+            }
+            ',' => {
+                if !in_value {
+                    return Err(ContextStringError {
+                        position,
+                        kind: ErrorKind::MissingEquals,
+                    });
+                }
+                entries.push((std::mem::take(&mut key), std::mem::take(&mut value)));
+                in_value = false;
+            }
+            _ => current.push(ch),
+        }
+    }
+
+    if !in_value {
+        return Err(ContextStringError {
+            position: encoded.len(),
+            kind: ErrorKind::MissingEquals,
+        });
+    }
+    entries.push((key, value));
+    Ok(entries)
+}
+
+fn escape_into(encoded: &mut String, raw: &str) {
+    for ch in raw.chars() {
+        match ch {
+            '\\' | ',' | '=' | '"' => {
+                encoded.push('\\');
+                encoded.push(ch);
+            }
+            '\n' => encoded.push_str("\\n"),
+            '\r' => encoded.push_str("\\r"),
+            '\t' => encoded.push_str("\\t"),
+            _ => encoded.push(ch),
+        }
+    }
+}
+
+/// Error returned by [`parse_context_string`] for malformed input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextStringError {
+    position: usize,
+    kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ErrorKind {
+    TrailingBackslash,
+    UnknownEscape(char),
+    UnescapedEquals,
+    MissingEquals,
+}
+
+impl ContextStringError {
+    /// Byte offset in the input where the problem was found
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for ContextStringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::TrailingBackslash => {
+                write!(f, "dangling `\\` at byte {}", self.position)
+            }
+            ErrorKind::UnknownEscape(ch) => {
+                write!(f, "unknown escape `\\{}` at byte {}", ch, self.position)
+            }
+            ErrorKind::UnescapedEquals => {
+                write!(f, "unescaped `=` in value at byte {}", self.position)
+            }
+            ErrorKind::MissingEquals => {
+                write!(f, "entry without `=` ending at byte {}", self.position)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ContextStringError {}
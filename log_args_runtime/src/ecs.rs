@@ -0,0 +1,195 @@
+//! Elastic Common Schema (ECS) output profile.
+//!
+//! [`EcsLayer`] writes one JSON document per event, mapping keys to their ECS names:
+//!
+//! ```text
+//! {"@timestamp":"...","log":{"level":"info","logger":"billing","origin":{"function":"charge_card"}},
+//!  "message":"Charging card","user":{"id":"42"},"labels":{"company_id":"7"},"ecs":{"version":"8.11.0"}}
+//! ```
+//!
+//! Keys found in the [`EcsMapping`] table are renamed, dotted keys such as `user.id` are
+//! nested into objects, and every other event field or inherited context key goes under
+//! `labels`.
+//!
+//! ```rust,ignore
+//! use tracing_subscriber::prelude::*;
+//!
+//! let mapping = log_args_runtime::EcsMapping::new().rename("tenant", "organization.id");
+//! tracing_subscriber::registry()
+//!     .with(log_args_runtime::EcsLayer::new().with_mapping(mapping))
+//!     .init();
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use serde_json::{Map, Value};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::get_merged_context;
+use crate::layer::event_fields;
+use crate::nested::insert_path;
+
+/// ECS version written to `ecs.version`
+const ECS_VERSION: &str = "8.11.0";
+
+/// Table of source keys and the ECS field each one is written to
+#[derive(Debug, Clone)]
+pub struct EcsMapping {
+    renames: BTreeMap<String, String>,
+}
+
+impl EcsMapping {
+    /// The standard mapping: `timestamp`, `level`, `message`, `target`, `function`,
+    /// `file` and `line` go to their ECS fields
+    pub fn new() -> Self {
+        Self::empty()
+            .rename("timestamp", "@timestamp")
+            .rename("level", "log.level")
+            .rename("message", "message")
+            .rename("target", "log.logger")
+            .rename("function", "log.origin.function")
+            .rename("file", "log.origin.file.name")
+            .rename("line", "log.origin.file.line")
+    }
+
+    /// A mapping without any renames; standard keys then end up under `labels`
+    pub fn empty() -> Self {
+        Self {
+            renames: BTreeMap::new(),
+        }
+    }
+
+    /// Write `key` to the ECS field `ecs_field`, replacing any existing rename of `key`
+    pub fn rename(mut self, key: impl Into<String>, ecs_field: impl Into<String>) -> Self {
+        self.renames.insert(key.into(), ecs_field.into());
+        self
+    }
+
+    /// The dotted ECS path `key` is written to
+    pub fn field_for(&self, key: &str) -> String {
+        match self.renames.get(key) {
+            Some(ecs_field) => ecs_field.clone(),
+            None if key.contains('.') => key.to_string(),
+            None => format!("labels.{}", key),
+        }
+    }
+}
+
+impl Default for EcsMapping {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// JSON layer that writes events and the inherited context as ECS documents
+pub struct EcsLayer<W = fn() -> std::io::Stdout> {
+    make_writer: W,
+    mapping: EcsMapping,
+}
+
+impl EcsLayer {
+    /// Create a layer writing to stdout with the standard mapping
+    pub fn new() -> Self {
+        Self {
+            make_writer: std::io::stdout,
+            mapping: EcsMapping::new(),
+        }
+    }
+}
+
+impl Default for EcsLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W> EcsLayer<W> {
+    /// Write to a different destination, e.g. a file or an in-memory buffer
+    pub fn with_writer<W2>(self, make_writer: W2) -> EcsLayer<W2>
+    where
+        W2: for<'writer> MakeWriter<'writer> + 'static,
+    {
+        EcsLayer {
+            make_writer,
+            mapping: self.mapping,
+        }
+    }
+
+    /// Replace the key mapping table
+    pub fn with_mapping(mut self, mapping: EcsMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+}
+
+impl<S, W> Layer<S> for EcsLayer<W>
+where
+    S: Subscriber,
+    W: for<'writer> MakeWriter<'writer> + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut timestamp = String::new();
+        if SystemTime
+            .format_time(&mut Writer::new(&mut timestamp))
+            .is_err()
+        {
+            timestamp.clear();
+        }
+
+        let mut entries: Vec<(String, Value)> = vec![
+            ("timestamp".to_string(), Value::String(timestamp)),
+            (
+                "level".to_string(),
+                Value::String(metadata.level().as_str().to_lowercase()),
+            ),
+            (
+                "target".to_string(),
+                Value::String(metadata.target().to_string()),
+            ),
+        ];
+        if let Some(file) = metadata.file() {
+            entries.push(("file".to_string(), Value::String(file.to_string())));
+        }
+        if let Some(line) = metadata.line() {
+            entries.push(("line".to_string(), Value::from(line)));
+        }
+
+        // Fields on the event itself win over inherited context with the same key
+        let fields = event_fields(event);
+        let context: BTreeMap<String, String> = get_merged_context()
+            .into_iter()
+            .filter(|(key, _)| !fields.contains_key(key))
+            .collect();
+        entries.extend(fields);
+        entries.extend(
+            context
+                .into_iter()
+                .map(|(key, value)| (key, Value::String(value))),
+        );
+
+        let mut document = Map::new();
+        for (key, value) in entries {
+            let path = self.mapping.field_for(&key);
+            if let Err(value) = insert_path(&mut document, &path, value, false) {
+                // The path runs into a value that isn't an object; keep the field as a label
+                let label = format!("labels.{}", key.replace('.', "_"));
+                let _ = insert_path(&mut document, &label, value, false);
+            }
+        }
+        let _ = insert_path(
+            &mut document,
+            "ecs.version",
+            Value::String(ECS_VERSION.to_string()),
+            false,
+        );
+
+        let mut writer = self.make_writer.make_writer_for(metadata);
+        let _ = writeln!(writer, "{}", Value::Object(document));
+    }
+}
@@ -0,0 +1,415 @@
+//! Single-emission logging with inherited context as individual fields.
+//!
+//! Tracing field names are fixed at compile time, but inherited context keys are only
+//! known at runtime. `log_with_context!` adds one last field to the user's log call,
+//! evaluated after every other argument, which makes a capturing dispatcher the default
+//! just for the dispatch of that event. When the call's block ends, the captured event is
+//! replayed to the real subscriber through a callsite whose field set is the user's fields
+//! followed by every context key. Callsites are created once per distinct (log call, key
+//! set) pair and cached.
+//!
+//! Callsites and the context keys naming their fields are leaked, so both are capped: at
+//! most [`MAX_CONTEXT_KEYS`] distinct keys and [`MAX_CONTEXT_CALLSITES`] callsites. An event
+//! that would need more keeps its own fields and carries its context in a single
+//! `context` field, through one more callsite per log call.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use once_cell::sync::{Lazy, OnceCell};
+use tracing::dispatcher::DefaultGuard;
+use tracing::field::{display, DisplayValue, Empty, Field, Value, Visit};
+use tracing::span;
+use tracing::subscriber::{Interest, NoSubscriber, Subscriber};
+use tracing::{Dispatch, Event, Metadata};
+use tracing_core::callsite::{self, Callsite, Identifier};
+use tracing_core::field::FieldSet;
+use tracing_core::metadata::{Kind, LevelFilter};
+
+/// Most distinct context keys that become fields of their own
+pub const MAX_CONTEXT_KEYS: usize = 1024;
+
+/// Most (log call, key set) callsites created for context fields
+pub const MAX_CONTEXT_CALLSITES: usize = 4096;
+
+/// Field holding the context of events past [`MAX_CONTEXT_KEYS`] or [`MAX_CONTEXT_CALLSITES`]
+const OVERFLOW_FIELD: &str = "context";
+
+/// One `log_with_context!` call. [`arm_capture`] marks it as captured, and the event is
+/// replayed with its context when the scope drops at the end of the call's block.
+///
+/// Each distinct context key and each distinct (log call, key set) pair is cached for the
+/// life of the process, up to [`MAX_CONTEXT_KEYS`] keys and [`MAX_CONTEXT_CALLSITES`]
+/// callsites. Once either limit is reached, events that would need a new key or callsite
+/// log their context as one `context` field, e.g. `{"region": "eu", "tenant": "acme"}`,
+/// so context keys taken from unbounded data can't grow memory without limit.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct CaptureScope {
+    armed: Cell<bool>,
+}
+
+/// Value of the last field of a `log_with_context!` event. The user's arguments have all
+/// been evaluated by then, under the caller's dispatcher, so this only makes the capture
+/// the default for the dispatch of the event itself.
+#[doc(hidden)]
+pub fn arm_capture(scope: &CaptureScope, context: HashMap<String, String>) -> Empty {
+    // Inside a subscriber callback there is no dispatcher to log to, and the current one
+    // can't be replaced
+    let active = tracing::dispatcher::get_default(|current| !current.is::<NoSubscriber>());
+    if active && !context.is_empty() {
+        let guard = tracing::dispatcher::set_default(&CAPTURE_DISPATCH);
+        CAPTURES.with(|captures| {
+            captures.borrow_mut().push(Capture {
+                guard,
+                context,
+                event: None,
+            })
+        });
+        scope.armed.set(true);
+    }
+    Empty
+}
+
+impl Drop for CaptureScope {
+    fn drop(&mut self) {
+        if !self.armed.get() {
+            return;
+        }
+        // Nothing awaits between arming and the end of the call, so the capture armed by
+        // this scope is the innermost one on this thread
+        let Some(capture) = CAPTURES.with(|captures| captures.borrow_mut().pop()) else {
+            return;
+        };
+        drop(capture.guard);
+        if std::thread::panicking() {
+            return;
+        }
+        if let Some(captured) = capture.event {
+            replay(captured, capture.context);
+        }
+    }
+}
+
+fn replay(captured: CapturedEvent, context: HashMap<String, String>) {
+    let event = ContextEvent::new(captured.metadata, captured.fields, context);
+    if event.callsite.interest.load(Ordering::Relaxed) == INTEREST_NEVER {
+        return;
+    }
+    let metadata = event.callsite.metadata();
+    tracing::dispatcher::get_default(|current| {
+        if current.enabled(metadata) {
+            event.with_event(captured.parent.clone(), |event| current.event(event));
+        }
+    });
+}
+
+/// An event's fields followed by the context entries it doesn't already carry,
+/// ready to be replayed through a runtime callsite
+pub(crate) struct ContextEvent {
+    callsite: &'static ContextCallsite,
+    values: Vec<CapturedValue>,
+}
+
+impl ContextEvent {
+    pub(crate) fn new(
+        original: &'static Metadata<'static>,
+        fields: Vec<(&'static str, CapturedValue)>,
+        context: HashMap<String, String>,
+    ) -> Self {
+        let mut names: Vec<&'static str> = fields.iter().map(|(name, _)| *name).collect();
+        let mut values: Vec<CapturedValue> = fields.into_iter().map(|(_, value)| value).collect();
+
+        let mut context: Vec<(String, String)> = context
+            .into_iter()
+            .filter(|(key, _)| !names.iter().any(|name| name == key))
+            .collect();
+        context.sort();
+
+        let context_names: Option<Vec<&'static str>> =
+            context.iter().map(|(key, _)| intern(key)).collect();
+        if let Some(context_names) = context_names {
+            let mut all_names = names.clone();
+            all_names.extend(context_names);
+            if let Some(callsite) = context_callsite(original, all_names, true) {
+                values.extend(
+                    context
+                        .into_iter()
+                        .map(|(_, value)| CapturedValue::Display(display(value))),
+                );
+                return ContextEvent { callsite, values };
+            }
+        }
+
+        // Past the limits, the whole context goes into one field of a callsite that exists
+        // once per log call
+        if !names.contains(&OVERFLOW_FIELD) {
+            let context: BTreeMap<String, String> = context.into_iter().collect();
+            names.push(OVERFLOW_FIELD);
+            values.push(CapturedValue::Display(display(format!("{:?}", context))));
+        }
+        let callsite = context_callsite(original, names, false)
+            .expect("uncapped context callsites are always created");
+        ContextEvent { callsite, values }
+    }
+
+    /// Build the merged event and hand it to `f`, as a child of `parent` when the original
+    /// event had an explicit parent (see [`explicit_parent`])
+    pub(crate) fn with_event<F: FnOnce(&Event<'_>)>(
+        &self,
+        parent: Option<Option<span::Id>>,
+        f: F,
+    ) {
+        let metadata = self.callsite.metadata();
+        let values: Vec<Option<&dyn Value>> =
+            self.values.iter().map(|value| Some(value.as_value())).collect();
+        let value_set = metadata.fields().value_set_all(&values);
+        let event = match parent {
+            Some(parent) => Event::new_child_of(parent, metadata, &value_set),
+            None => Event::new(metadata, &value_set),
+        };
+        f(&event);
+    }
+}
+
+/// The parent `event` was given with `parent:`, if any; `Some(None)` for a root event
+pub(crate) fn explicit_parent(event: &Event<'_>) -> Option<Option<span::Id>> {
+    if event.is_contextual() {
+        None
+    } else {
+        Some(event.parent().cloned())
+    }
+}
+
+// ===== capturing the user's event =====
+
+struct Capture {
+    guard: DefaultGuard,
+    context: HashMap<String, String>,
+    event: Option<CapturedEvent>,
+}
+
+struct CapturedEvent {
+    metadata: &'static Metadata<'static>,
+    fields: Vec<(&'static str, CapturedValue)>,
+    parent: Option<Option<span::Id>>,
+}
+
+pub(crate) enum CapturedValue {
+    F64(f64),
+    I64(i64),
+    U64(u64),
+    Bool(bool),
+    Str(String),
+    // Debug and Display values are rendered once and replayed verbatim
+    Display(DisplayValue<String>),
+}
+
+impl CapturedValue {
+    fn as_value(&self) -> &dyn Value {
+        match self {
+            CapturedValue::F64(value) => value,
+            CapturedValue::I64(value) => value,
+            CapturedValue::U64(value) => value,
+            CapturedValue::Bool(value) => value,
+            CapturedValue::Str(value) => value,
+            CapturedValue::Display(value) => value,
+        }
+    }
+}
+
+thread_local! {
+    static CAPTURES: RefCell<Vec<Capture>> = const { RefCell::new(Vec::new()) };
+}
+
+static CAPTURE_DISPATCH: Lazy<Dispatch> = Lazy::new(|| Dispatch::new(CaptureSubscriber));
+
+/// Subscriber that records the event of the innermost armed `log_with_context!` call.
+/// It is the default only between the last argument of that call being evaluated and the
+/// event being dispatched, so that event is the only one it ever receives.
+///
+/// Like any registered dispatcher, it takes part in the interest of every callsite in the
+/// process: since it never claims interest, a callsite the real subscriber is `always`
+/// interested in becomes `sometimes`, and its `enabled` is asked on each event.
+struct CaptureSubscriber;
+
+impl Subscriber for CaptureSubscriber {
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        Interest::never()
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(LevelFilter::OFF)
+    }
+
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut visitor = CaptureVisitor::default();
+        event.record(&mut visitor);
+        CAPTURES.with(|captures| {
+            if let Some(capture) = captures.borrow_mut().last_mut() {
+                capture.event = Some(CapturedEvent {
+                    metadata: event.metadata(),
+                    fields: visitor.fields,
+                    parent: explicit_parent(event),
+                });
+            }
+        });
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+#[derive(Default)]
+pub(crate) struct CaptureVisitor {
+    pub(crate) fields: Vec<(&'static str, CapturedValue)>,
+}
+
+impl Visit for CaptureVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.push((field.name(), CapturedValue::F64(value)));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.push((field.name(), CapturedValue::I64(value)));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.push((field.name(), CapturedValue::U64(value)));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.push((field.name(), CapturedValue::Bool(value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields
+            .push((field.name(), CapturedValue::Str(value.to_string())));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.fields.push((
+            field.name(),
+            CapturedValue::Display(display(format!("{:?}", value))),
+        ));
+    }
+}
+
+// ===== runtime callsites =====
+
+const INTEREST_NEVER: u8 = 0;
+const INTEREST_SOMETIMES: u8 = 1;
+const INTEREST_ALWAYS: u8 = 2;
+
+struct ContextCallsite {
+    metadata: OnceCell<Metadata<'static>>,
+    interest: AtomicU8,
+}
+
+impl ContextCallsite {
+    fn metadata(&'static self) -> &'static Metadata<'static> {
+        self.metadata
+            .get()
+            .expect("context callsite metadata is set before registration")
+    }
+}
+
+impl Callsite for ContextCallsite {
+    fn set_interest(&self, interest: Interest) {
+        let interest = if interest.is_never() {
+            INTEREST_NEVER
+        } else if interest.is_always() {
+            INTEREST_ALWAYS
+        } else {
+            INTEREST_SOMETIMES
+        };
+        self.interest.store(interest, Ordering::Relaxed);
+    }
+
+    fn metadata(&self) -> &Metadata<'_> {
+        self.metadata
+            .get()
+            .expect("context callsite metadata is set before registration")
+    }
+}
+
+type CallsiteKey = (usize, Vec<&'static str>);
+
+static CALLSITES: Lazy<Mutex<HashMap<CallsiteKey, &'static ContextCallsite>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static CAPPED_CALLSITES: AtomicUsize = AtomicUsize::new(0);
+
+static FIELD_NAMES: Lazy<Mutex<HashSet<&'static str>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
+
+// Context keys must be 'static to name fields; each distinct key is leaked once,
+// up to `MAX_CONTEXT_KEYS` keys
+fn intern(name: &str) -> Option<&'static str> {
+    let mut names = FIELD_NAMES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(interned) = names.get(name) {
+        return Some(interned);
+    }
+    if names.len() >= MAX_CONTEXT_KEYS {
+        return None;
+    }
+    let interned: &'static str = Box::leak(name.to_string().into_boxed_str());
+    names.insert(interned);
+    Some(interned)
+}
+
+// Capped callsites count towards `MAX_CONTEXT_CALLSITES`. Overflow callsites aren't capped,
+// as there is at most one per log call in the program.
+fn context_callsite(
+    original: &'static Metadata<'static>,
+    names: Vec<&'static str>,
+    capped: bool,
+) -> Option<&'static ContextCallsite> {
+    let key = (original as *const Metadata<'static> as usize, names);
+    let mut callsites = CALLSITES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(callsite) = callsites.get(&key) {
+        return Some(callsite);
+    }
+    if capped && CAPPED_CALLSITES.load(Ordering::Relaxed) >= MAX_CONTEXT_CALLSITES {
+        return None;
+    }
+
+    let names: &'static [&'static str] = Box::leak(key.1.clone().into_boxed_slice());
+    let callsite: &'static ContextCallsite = Box::leak(Box::new(ContextCallsite {
+        metadata: OnceCell::new(),
+        interest: AtomicU8::new(INTEREST_SOMETIMES),
+    }));
+    let metadata = Metadata::new(
+        original.name(),
+        original.target(),
+        *original.level(),
+        original.file(),
+        original.line(),
+        original.module_path(),
+        FieldSet::new(names, Identifier(callsite)),
+        Kind::EVENT,
+    );
+    let _ = callsite.metadata.set(metadata);
+    callsite::register(callsite);
+
+    callsites.insert(key, callsite);
+    if capped {
+        CAPPED_CALLSITES.fetch_add(1, Ordering::Relaxed);
+    }
+    Some(callsite)
+}
@@ -0,0 +1,172 @@
+//! GELF 1.1 output for Graylog.
+//!
+//! [`GelfLayer`] writes one GELF JSON message per event. The event's fields and the
+//! inherited context become additional `_`-prefixed fields, with `function` first:
+//!
+//! ```text
+//! {"version":"1.1","host":"api-1","short_message":"Charging card","timestamp":1714564800.123,
+//!  "level":6,"_function":"charge_card","_target":"billing","_amount":100,"_company_id":"42"}
+//! ```
+//!
+//! Messages are terminated with a null byte by default, as Graylog's TCP input expects.
+//! Each message is handed to the writer in a single `write_all`, so a writer that sends
+//! one UDP datagram per write can be used with [`GelfLayer::with_delimiter`]`(None)`.
+//!
+//! ```rust,ignore
+//! use std::net::TcpStream;
+//! use std::sync::Mutex;
+//! use tracing_subscriber::prelude::*;
+//!
+//! let stream = TcpStream::connect("graylog:12201")?;
+//! tracing_subscriber::registry()
+//!     .with(log_args_runtime::GelfLayer::new().with_writer(Mutex::new(stream)))
+//!     .init();
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{Map, Value};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::get_merged_context;
+use crate::layer::event_fields;
+use crate::syslog::{default_hostname, severity};
+
+/// Layer that writes events and the inherited context as GELF 1.1 messages
+pub struct GelfLayer<W = fn() -> std::io::Stdout> {
+    make_writer: W,
+    host: String,
+    delimiter: Option<u8>,
+}
+
+impl GelfLayer {
+    /// Create a layer writing null-terminated messages to stdout, using the `HOSTNAME`
+    /// environment variable (or `localhost`) as the host
+    pub fn new() -> Self {
+        Self {
+            make_writer: std::io::stdout,
+            host: default_hostname().unwrap_or_else(|| "localhost".to_string()),
+            delimiter: Some(b'\0'),
+        }
+    }
+}
+
+impl Default for GelfLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W> GelfLayer<W> {
+    /// Write to a different destination, e.g. `Mutex<TcpStream>` or an in-memory buffer
+    pub fn with_writer<W2>(self, make_writer: W2) -> GelfLayer<W2>
+    where
+        W2: for<'writer> MakeWriter<'writer> + 'static,
+    {
+        GelfLayer {
+            make_writer,
+            host: self.host,
+            delimiter: self.delimiter,
+        }
+    }
+
+    /// The `host` field of every message
+    pub fn with_host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Byte written after each message; `None` for UDP, where every datagram is one message
+    pub fn with_delimiter(mut self, delimiter: Option<u8>) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+}
+
+impl<S, W> Layer<S> for GelfLayer<W>
+where
+    S: Subscriber,
+    W: for<'writer> MakeWriter<'writer> + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut fields = event_fields(event);
+        let mut context: BTreeMap<String, String> = get_merged_context()
+            .into_iter()
+            .filter(|(key, _)| !fields.contains_key(key))
+            .collect();
+
+        let short_message = match fields.remove("message") {
+            Some(Value::String(message)) if !message.is_empty() => message,
+            Some(message) if !message.is_null() => message.to_string(),
+            _ => metadata.name().to_string(),
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| (elapsed.as_millis() as f64) / 1000.0)
+            .unwrap_or_default();
+
+        let mut message = Map::new();
+        message.insert("version".to_string(), Value::from("1.1"));
+        message.insert("host".to_string(), Value::from(self.host.as_str()));
+        message.insert("short_message".to_string(), Value::from(short_message));
+        message.insert("timestamp".to_string(), Value::from(timestamp));
+        message.insert("level".to_string(), Value::from(severity(metadata.level())));
+
+        // The function name comes from the event, or else from the innermost decorated function
+        let function = fields
+            .remove("function")
+            .or_else(|| context.remove("function").map(Value::from));
+        if let Some(function) = function {
+            insert_additional(&mut message, "function", function);
+        }
+        insert_additional(&mut message, "target", Value::from(metadata.target()));
+        if let Some(file) = metadata.file() {
+            insert_additional(&mut message, "file", Value::from(file));
+        }
+        if let Some(line) = metadata.line() {
+            insert_additional(&mut message, "line", Value::from(line));
+        }
+        for (key, value) in fields {
+            insert_additional(&mut message, &key, value);
+        }
+        for (key, value) in context {
+            insert_additional(&mut message, &key, Value::from(value));
+        }
+
+        let mut encoded = Value::Object(message).to_string().into_bytes();
+        if let Some(delimiter) = self.delimiter {
+            encoded.push(delimiter);
+        }
+        let mut writer = self.make_writer.make_writer_for(metadata);
+        let _ = writer.write_all(&encoded);
+    }
+}
+
+// Additional fields are `_`-prefixed, limited to `[A-Za-z0-9_.-]`, and must be strings or
+// numbers. `_id` is reserved by GELF, so an `id` field is written as `__id`.
+fn insert_additional(message: &mut Map<String, Value>, key: &str, value: Value) {
+    let mut name = String::with_capacity(key.len() + 1);
+    name.push('_');
+    name.extend(key.chars().map(|ch| {
+        if ch.is_ascii_alphanumeric() || ch == '_' || ch == '.' || ch == '-' {
+            ch
+        } else {
+            '_'
+        }
+    }));
+    if name == "_id" {
+        name.insert(0, '_');
+    }
+
+    let value = match value {
+        Value::String(_) | Value::Number(_) => value,
+        Value::Null => return,
+        other => Value::from(other.to_string()),
+    };
+    message.entry(name).or_insert(value);
+}
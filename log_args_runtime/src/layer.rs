@@ -0,0 +1,301 @@
+//! A `tracing-subscriber` layer that writes JSON events with the inherited context
+//! flattened into real fields.
+//!
+//! Children that log with plain `tracing::info!` don't see the parent's context at all.
+//! `LogArgsLayer` reads the runtime context on every event and writes each key as its
+//! own field, so log pipelines can index it:
+//!
+//! ```rust,ignore
+//! use tracing_subscriber::prelude::*;
+//!
+//! tracing_subscriber::registry()
+//!     .with(log_args_runtime::LogArgsLayer::new())
+//!     .init();
+//! ```
+//!
+//! [`LogArgsLayer::nest_dotted_keys`] expands keys such as `person.contact.email` into
+//! nested objects.
+//!
+//! To keep an existing formatter, wrap it in [`ContextLayer`] instead. Every event it
+//! receives, including plain `tracing::info!` calls in undecorated helpers, gets the
+//! inherited context added as fields before the wrapped layer sees it:
+//!
+//! ```rust,ignore
+//! use tracing_subscriber::prelude::*;
+//!
+//! tracing_subscriber::registry()
+//!     .with(log_args_runtime::ContextLayer::new(tracing_subscriber::fmt::layer().json()))
+//!     .init();
+//! ```
+//!
+//! With the `bunyan` feature, [`ContextLayer::bunyan`] wraps `tracing-bunyan-formatter`'s
+//! `BunyanFormattingLayer`, so inherited context keys become top-level Bunyan properties.
+
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::subscriber::Interest;
+use tracing::{span, Dispatch, Event, Metadata, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::event::{explicit_parent, CaptureVisitor, ContextEvent};
+use crate::get_merged_context;
+use crate::nested::{nest_dotted_keys, DottedKeyConflict};
+
+/// JSON formatting layer that adds every runtime context field to each event
+pub struct LogArgsLayer<W = fn() -> std::io::Stdout> {
+    make_writer: W,
+    flatten_event: bool,
+    nest_dotted_keys: Option<DottedKeyConflict>,
+}
+
+impl LogArgsLayer {
+    /// Create a layer writing to stdout
+    pub fn new() -> Self {
+        Self {
+            make_writer: std::io::stdout,
+            flatten_event: false,
+            nest_dotted_keys: None,
+        }
+    }
+}
+
+impl Default for LogArgsLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W> LogArgsLayer<W> {
+    /// Write to a different destination, e.g. a file or an in-memory buffer
+    pub fn with_writer<W2>(self, make_writer: W2) -> LogArgsLayer<W2>
+    where
+        W2: for<'writer> MakeWriter<'writer> + 'static,
+    {
+        LogArgsLayer {
+            make_writer,
+            flatten_event: self.flatten_event,
+            nest_dotted_keys: self.nest_dotted_keys,
+        }
+    }
+
+    /// Put event and context fields at the top level of the JSON object instead of
+    /// under `fields`, like `tracing_subscriber::fmt::format::Json::flatten_event`
+    pub fn flatten_event(mut self, flatten_event: bool) -> Self {
+        self.flatten_event = flatten_event;
+        self
+    }
+
+    /// Expand dotted keys such as `person.contact.email` into nested objects, resolving
+    /// clashes between a scalar and an object with `conflict`
+    pub fn nest_dotted_keys(mut self, conflict: DottedKeyConflict) -> Self {
+        self.nest_dotted_keys = Some(conflict);
+        self
+    }
+}
+
+impl<S, W> Layer<S> for LogArgsLayer<W>
+where
+    S: Subscriber,
+    W: for<'writer> MakeWriter<'writer> + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut fields = event_fields(event);
+
+        // Fields on the event itself win over inherited context with the same key
+        let context: BTreeMap<String, String> = get_merged_context().into_iter().collect();
+        for (key, value) in context {
+            fields.entry(key).or_insert(Value::String(value));
+        }
+        if let Some(conflict) = self.nest_dotted_keys {
+            fields = nest_dotted_keys(fields, conflict);
+        }
+
+        let mut timestamp = String::new();
+        if SystemTime.format_time(&mut Writer::new(&mut timestamp)).is_err() {
+            timestamp.clear();
+        }
+
+        let mut line = Map::new();
+        line.insert("timestamp".to_string(), Value::String(timestamp));
+        line.insert(
+            "level".to_string(),
+            Value::String(event.metadata().level().to_string()),
+        );
+        if self.flatten_event {
+            line.extend(fields);
+        } else {
+            line.insert("fields".to_string(), Value::Object(fields));
+        }
+        line.insert(
+            "target".to_string(),
+            Value::String(event.metadata().target().to_string()),
+        );
+
+        let mut writer = self.make_writer.make_writer_for(event.metadata());
+        let _ = writeln!(writer, "{}", Value::Object(line));
+    }
+}
+
+/// Wraps another layer so every event it sees carries the inherited context as fields
+pub struct ContextLayer<L> {
+    inner: L,
+}
+
+impl<L> ContextLayer<L> {
+    /// Add the runtime context to the events passed to `inner`
+    pub fn new(inner: L) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped layer
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+}
+
+impl<S, L> Layer<S> for ContextLayer<L>
+where
+    S: Subscriber,
+    L: Layer<S>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut context = get_merged_context();
+        if context.is_empty() {
+            return self.inner.on_event(event, ctx);
+        }
+
+        let mut visitor = CaptureVisitor::default();
+        event.record(&mut visitor);
+        // Events from `log_with_context!` already carry every key
+        for (name, _) in &visitor.fields {
+            context.remove(*name);
+        }
+        if context.is_empty() {
+            return self.inner.on_event(event, ctx);
+        }
+
+        let merged = ContextEvent::new(event.metadata(), visitor.fields, context);
+        merged.with_event(explicit_parent(event), |merged| self.inner.on_event(merged, ctx));
+    }
+
+    fn on_register_dispatch(&self, subscriber: &Dispatch) {
+        self.inner.on_register_dispatch(subscriber);
+    }
+
+    fn on_layer(&mut self, subscriber: &mut S) {
+        self.inner.on_layer(subscriber);
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.enabled(metadata, ctx)
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.event_enabled(event, ctx)
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        self.inner.max_level_hint()
+    }
+
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_new_span(attrs, id, ctx);
+    }
+
+    fn on_record(&self, span: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        self.inner.on_record(span, values, ctx);
+    }
+
+    fn on_follows_from(&self, span: &span::Id, follows: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_follows_from(span, follows, ctx);
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        self.inner.on_close(id, ctx);
+    }
+
+    fn on_id_change(&self, old: &span::Id, new: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_id_change(old, new, ctx);
+    }
+
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            Some(self as *const Self as *const ())
+        } else {
+            self.inner.downcast_raw(id)
+        }
+    }
+}
+
+#[cfg(feature = "bunyan")]
+impl<W> ContextLayer<tracing_bunyan_formatter::BunyanFormattingLayer<W>>
+where
+    W: for<'writer> MakeWriter<'writer> + 'static,
+{
+    /// Bunyan-formatted output where every inherited context key is a top-level property
+    pub fn bunyan(name: impl Into<String>, make_writer: W) -> Self {
+        Self::new(tracing_bunyan_formatter::BunyanFormattingLayer::new(
+            name.into(),
+            make_writer,
+        ))
+    }
+}
+
+/// Collect an event's fields into a JSON object
+pub(crate) fn event_fields(event: &Event<'_>) -> Map<String, Value> {
+    let mut visitor = JsonVisitor::default();
+    event.record(&mut visitor);
+    visitor.fields
+}
+
+#[derive(Default)]
+struct JsonVisitor {
+    fields: Map<String, Value>,
+}
+
+impl Visit for JsonVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.fields
+            .insert(field.name().to_string(), Value::from(format!("{:?}", value)));
+    }
+}
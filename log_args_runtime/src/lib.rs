@@ -1,20 +1,79 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 
+mod context_string;
+mod ecs;
+mod event;
+mod gelf;
+mod layer;
+#[cfg(feature = "log")]
+mod log_bridge;
+mod logfmt;
+mod nested;
+mod request_id;
+mod scanner;
+mod scope;
+mod syslog;
+
+pub use context_string::{
+    format_context_string, parse_context_string, ContextStringError, NO_CONTEXT,
+};
+pub use ecs::{EcsLayer, EcsMapping};
+pub use event::{arm_capture, CaptureScope, MAX_CONTEXT_CALLSITES, MAX_CONTEXT_KEYS};
+pub use gelf::GelfLayer;
+pub use layer::{ContextLayer, LogArgsLayer};
+#[cfg(feature = "log")]
+pub use log_bridge::ContextLogger;
+pub use logfmt::LogfmtFormat;
+pub use nested::DottedKeyConflict;
+#[doc(hidden)]
+#[cfg(feature = "request-id")]
+pub use request_id::request_id_or_new;
+pub use request_id::{
+    request_id_format, request_id_from_headers, set_request_id_format, IntoRequestId,
+    RequestIdFormat, REQUEST_ID_HEADERS,
+};
+#[cfg(feature = "request-id")]
+pub use request_id::new_request_id;
+// Used by `__log_compat!`
+#[cfg(feature = "log")]
+#[doc(hidden)]
+pub use log as __log;
+pub use scanner::{
+    redaction_counts, reset_redaction_counts, scan_value, sensitive_scanner_enabled,
+    set_sensitive_scanner, RedactionCounts, Scanned,
+};
+#[doc(hidden)]
+pub use scope::{enter_isolated_scope, enter_root_scope, ScopeGuard};
+pub use syslog::{SyslogFraming, SyslogLayer};
+
 // Global context store for cross-boundary persistence
-static GLOBAL_CONTEXT: std::sync::LazyLock<Arc<Mutex<HashMap<String, String>>>> = 
-    std::sync::LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
+static GLOBAL_CONTEXT: once_cell::sync::Lazy<Arc<Mutex<HashMap<String, String>>>> = 
+    once_cell::sync::Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+/// `Type::function` for a method, from `std::any::type_name::<Self>()` without the module
+/// path or generic arguments
+#[doc(hidden)]
+pub fn qualified_function_name(type_name: &str, function: &str) -> String {
+    let type_name = type_name.split('<').next().unwrap_or(type_name);
+    let type_name = type_name.rsplit("::").next().unwrap_or(type_name);
+    format!("{}::{}", type_name, function)
+}
 
 /// Set global context that persists across all boundaries
 pub fn set_global_context(key: &str, value: &str) {
     if let Ok(mut global) = GLOBAL_CONTEXT.lock() {
-        global.insert(key.to_string(), value.to_string());
+        global.insert(key.to_string(), scan_value(value));
     }
 }
 
 /// Get global context for cross-boundary persistence
 pub fn get_global_context() -> Option<HashMap<String, String>> {
+    if scope::global_context_hidden() {
+        return None;
+    }
     if let Ok(global) = GLOBAL_CONTEXT.lock() {
         if !global.is_empty() {
             return Some(global.clone());
@@ -23,9 +82,128 @@ pub fn get_global_context() -> Option<HashMap<String, String>> {
     None
 }
 
+// Key for pseudonymous field hashing, supplied once at startup
+#[cfg(feature = "hash")]
+static HASH_KEY: Mutex<Option<Vec<u8>>> = Mutex::new(None);
+
+/// Number of HMAC-SHA256 bytes kept in a hashed field (rendered as 32 hex characters)
+#[cfg(feature = "hash")]
+const HASH_BYTES: usize = 16;
+
+/// Set the secret key used by `#[params(hash(...))]` fields.
+/// Call this once at startup; calling it again rotates the key for subsequent logs.
+#[cfg(feature = "hash")]
+pub fn init_hash_key(key: &[u8]) {
+    if let Ok(mut hash_key) = HASH_KEY.lock() {
+        *hash_key = Some(key.to_vec());
+    }
+}
+
+/// Hash a value with the configured key so it can be correlated across logs
+/// without being stored. Returns `<hash_key_unset>` until `init_hash_key` is called,
+/// so raw values never leak through an unkeyed digest.
+#[cfg(feature = "hash")]
+pub fn hash_value(value: &str) -> String {
+    use hmac::{Hmac, Mac};
+
+    let Ok(hash_key) = HASH_KEY.lock() else {
+        return "<hash_key_unset>".to_string();
+    };
+    let Some(key) = hash_key.as_ref() else {
+        return "<hash_key_unset>".to_string();
+    };
+    let Ok(mut mac) = Hmac::<sha2::Sha256>::new_from_slice(key) else {
+        return "<hash_key_unset>".to_string();
+    };
+    mac.update(value.as_bytes());
+    mac.finalize().into_bytes()[..HASH_BYTES]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Hash a field for code generated by `#[params(hash(...))]`
+#[cfg(feature = "hash")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __hash_field {
+    ($value:expr) => {
+        $crate::hash_value($value)
+    };
+}
+
+// Without the `hash` feature, `hash(...)` fails with an explanation instead of an
+// unresolved function
+#[cfg(not(feature = "hash"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __hash_field {
+    ($value:expr) => {
+        compile_error!("`#[params(hash(...))]` needs the `hash` feature of log-args-runtime")
+    };
+}
+
+/// Request id for code generated by `#[params(root)]` and `#[params(request_id = ...)]`
+#[cfg(feature = "request-id")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __request_id {
+    () => {
+        $crate::new_request_id()
+    };
+    ($id:expr) => {
+        $crate::request_id_or_new($id)
+    };
+}
+
+// Without the `request-id` feature, generating an id fails with an explanation instead of
+// an unresolved function
+#[cfg(not(feature = "request-id"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __request_id {
+    () => {
+        compile_error!("`#[params(root)]` needs the `request-id` feature of log-args-runtime to generate request ids")
+    };
+    ($id:expr) => {
+        compile_error!("`#[params(request_id = ...)]` needs the `request-id` feature of log-args-runtime")
+    };
+}
+
+/// Log through the `log` facade for code generated by `#[params(log_compat)]`
+#[cfg(feature = "log")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_compat {
+    ($level:ident, target: $target:expr, $($args:tt)*) => {
+        $crate::__log::log!(target: $target, $crate::__log::Level::$level, $($args)*)
+    };
+}
+
+// Without the `log` feature, `log_compat` fails with an explanation instead of an
+// unresolved module
+#[cfg(not(feature = "log"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_compat {
+    ($($args:tt)*) => {
+        compile_error!("`#[params(log_compat)]` needs the `log` feature of log-args-runtime")
+    };
+}
+
+static NEXT_CALL_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Id of one invocation of a decorated function, unique within the process
+#[doc(hidden)]
+pub fn next_call_id() -> u64 {
+    NEXT_CALL_ID.fetch_add(1, Ordering::Relaxed)
+}
+
 // Thread-local storage for context stacks
 thread_local! {
+    #[allow(clippy::missing_const_for_thread_local)]
     static CONTEXT_STACK: RefCell<Vec<HashMap<String, String>>> = RefCell::new(Vec::new());
+    #[allow(clippy::missing_const_for_thread_local)]
     static ASYNC_CONTEXT_STACK: RefCell<Vec<HashMap<String, String>>> = RefCell::new(Vec::new());
 }
 
@@ -68,6 +246,9 @@ pub fn get_context_value(key: &str) -> Option<String> {
     }
     
     // Finally, try global context store for cross-boundary persistence
+    if scope::global_context_hidden() {
+        return None;
+    }
     if let Ok(global) = GLOBAL_CONTEXT.lock() {
         if let Some(value) = global.get(key) {
             return Some(value.clone());
@@ -77,6 +258,47 @@ pub fn get_context_value(key: &str) -> Option<String> {
     None
 }
 
+/// Get every context field inherited through the sync and async stacks, async frames over
+/// sync frames and innermost frames first. This is the context the logging macros add:
+/// unlike `get_merged_context`, it leaves out the global store, which keeps the custom
+/// fields of every function that has run so far.
+pub fn get_inherited_context() -> HashMap<String, String> {
+    let mut inherited = get_context();
+    inherited.extend(get_async_context());
+    inherited
+}
+
+/// Get every inherited context field, with the same precedence as `get_context_value`:
+/// async frames over sync frames over the global store, innermost frames first
+pub fn get_merged_context() -> HashMap<String, String> {
+    let mut merged = get_global_context().unwrap_or_default();
+    merged.extend(get_inherited_context());
+    merged
+}
+
+/// `call_path` and `depth` of a call to `function`: the `function` of every frame on the
+/// stack, outermost first and with async frames inside sync ones as in
+/// `get_merged_context`, followed by `function` itself and joined with `>`. A path of more
+/// than `max_len` functions keeps the innermost ones after a leading `...`.
+#[doc(hidden)]
+pub fn call_path(function: &str, max_len: usize) -> (String, usize) {
+    let mut functions: Vec<String> = Vec::new();
+    let mut collect = |stack: &Vec<HashMap<String, String>>| {
+        functions.extend(stack.iter().filter_map(|frame| frame.get("function").cloned()));
+    };
+    CONTEXT_STACK.with(|stack| collect(&stack.borrow()));
+    let _ = ASYNC_CONTEXT_STACK.try_with(|stack| collect(&stack.borrow()));
+    functions.push(function.to_string());
+
+    let depth = functions.len();
+    let max_len = max_len.max(1);
+    if depth > max_len {
+        functions.drain(..depth - max_len);
+        functions.insert(0, "...".to_string());
+    }
+    (functions.join(">"), depth)
+}
+
 /// Get current synchronous context
 #[doc(hidden)]
 pub fn get_context() -> HashMap<String, String> {
@@ -113,9 +335,24 @@ pub fn get_current_async_stack() -> Vec<HashMap<String, String>> {
         .unwrap_or_else(|_| vec![HashMap::new()])
 }
 
+// Mask sensitive values before a frame is pushed, when the scanner is enabled
+fn scan_context(context: HashMap<String, String>) -> HashMap<String, String> {
+    if !sensitive_scanner_enabled() {
+        return context;
+    }
+    context
+        .into_iter()
+        .map(|(key, value)| {
+            let value = scan_value(&value);
+            (key, value)
+        })
+        .collect()
+}
+
 /// Push context for synchronous functions with span
 #[doc(hidden)]
 pub fn push_context(context: HashMap<String, String>) -> ContextGuard {
+    let context = scan_context(context);
     CONTEXT_STACK.with(|stack| {
         stack.borrow_mut().push(context);
     });
@@ -125,6 +362,7 @@ pub fn push_context(context: HashMap<String, String>) -> ContextGuard {
 /// Push context for asynchronous functions with span
 #[doc(hidden)]
 pub fn push_async_context(context: HashMap<String, String>) -> AsyncContextGuard {
+    let context = scan_context(context);
     ASYNC_CONTEXT_STACK.with(|stack| {
         stack.borrow_mut().push(context);
     });
@@ -170,94 +408,130 @@ macro_rules! add_context_fields {
     };
 }
 
+/// Log with the given context map, emitting exactly one event in which every
+/// context entry is its own structured field alongside the macro's own fields.
+///
+/// The call expands to a block in the caller's scope, so its arguments may use `?` and
+/// `.await`, and events they log are kept. An explicit `parent:` is kept as well.
+///
+/// Context keys and key sets are cached for the life of the process, up to
+/// [`MAX_CONTEXT_KEYS`] keys and [`MAX_CONTEXT_CALLSITES`] key sets; past either limit the
+/// context is logged as a single `context` field instead.
 #[macro_export]
 macro_rules! log_with_context {
     ($log_macro:path, $context:expr, $($args:tt)*) => {
-        {
-            let ctx = $context;
-            if ctx.is_empty() {
-                $log_macro!($($args)*);
-            } else {
-                // Completely dynamic approach - NO hardcoded field names whatsoever
-                // Since Rust macros cannot dynamically generate field names at compile time,
-                // we use a runtime approach that works with any field names
-                
-                // Create individual log fields dynamically using tracing's structured logging
-                // This approach works with any field names without hardcoding
-                
-                // Create a span with dynamic fields and log within it
-                let span = ::tracing::info_span!(
-                    "context",
-                    // We can't dynamically create field names in the span macro
-                    // So we'll use the record API instead
-                );
-                
-                // Record all context fields dynamically
-                for (key, value) in ctx.iter() {
-                    span.record(key.as_str(), &::tracing::field::display(value));
-                }
-                
-                // Execute the log within the context span
-                let _enter = span.enter();
-                $log_macro!($($args)*);
-                
-                // Alternative: if span approach doesn't work, fall back to context string
-                // This ensures we never lose context information
-                drop(_enter);
-                drop(span);
-                
-                // Fallback: create a single context field with all data
-                let mut context_fields = Vec::new();
-                for (key, value) in ctx.iter() {
-                    context_fields.push(format!("{}={}", key, value));
-                }
-                if !context_fields.is_empty() {
-                    let context_data = context_fields.join(" ");
-                    // This logs context as a single structured field
-                    // Individual fields will be available through the span above
-                    $log_macro!(context = %context_data, $($args)*);
-                } else {
-                    $log_macro!($($args)*);
-                }
-            }
-        }
+        $crate::__log_with_context!($log_macro, $context, [] $($args)*)
     };
 }
 
+// Moves `name:`, `target:` and `parent:` into the prefix, which `event!` callers also use
+// for the level, then walks the fields the way `tracing` parses them. The capture is armed
+// by one more field after them, so it is evaluated after every other argument, the message
+// included: `tracing` evaluates the message first.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_with_context {
+    // === named arguments ===
+    (@fields $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] name: $e:expr, $($rest:tt)*) => {
+        $crate::__log_with_context!(@fields $scope, $m, $ctx, [$($p)* name: $e,] [$($f)*] $($rest)*)
+    };
+    (@fields $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] target: $e:expr, $($rest:tt)*) => {
+        $crate::__log_with_context!(@fields $scope, $m, $ctx, [$($p)* target: $e,] [$($f)*] $($rest)*)
+    };
+    (@fields $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] parent: $e:expr, $($rest:tt)*) => {
+        $crate::__log_with_context!(@fields $scope, $m, $ctx, [$($p)* parent: $e,] [$($f)*] $($rest)*)
+    };
+
+    // === keys ===
+    (@fields $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] $($k:ident).+ = $($rest:tt)*) => {
+        $crate::__log_with_context!(@value $scope, $m, $ctx, [$($p)*] [$($f)* $($k).+ =] $($rest)*)
+    };
+    (@fields $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] $k:literal = $($rest:tt)*) => {
+        $crate::__log_with_context!(@value $scope, $m, $ctx, [$($p)*] [$($f)* $k =] $($rest)*)
+    };
+    (@fields $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] { $($k:tt)* } = $($rest:tt)*) => {
+        $crate::__log_with_context!(@value $scope, $m, $ctx, [$($p)*] [$($f)* { $($k)* } =] $($rest)*)
+    };
+
+    // === shorthand fields ===
+    (@fields $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] $($k:ident).+ $(, $($rest:tt)*)?) => {
+        $crate::__log_with_context!(@fields $scope, $m, $ctx, [$($p)*] [$($f)* $($k).+,] $($($rest)*)?)
+    };
+    (@fields $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] ?$($k:ident).+ $(, $($rest:tt)*)?) => {
+        $crate::__log_with_context!(@fields $scope, $m, $ctx, [$($p)*] [$($f)* ?$($k).+,] $($($rest)*)?)
+    };
+    (@fields $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] %$($k:ident).+ $(, $($rest:tt)*)?) => {
+        $crate::__log_with_context!(@fields $scope, $m, $ctx, [$($p)*] [$($f)* %$($k).+,] $($($rest)*)?)
+    };
+
+    // === fields in braces ===
+    (@fields $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] { $($fields:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::__log_with_context!(@fields $scope, $m, $ctx, [$($p)*] [$($f)*] $($fields)*, $($($rest)*)?)
+    };
+    (@fields $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] , $($rest:tt)*) => {
+        $crate::__log_with_context!(@fields $scope, $m, $ctx, [$($p)*] [$($f)*] $($rest)*)
+    };
+
+    // === the message, if any, and the capture ===
+    (@fields $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*]) => {
+        $m!($($p)* $($f)* __log_args_capture = $crate::arm_capture(&$scope, $ctx))
+    };
+    (@fields $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] $($message:tt)+) => {
+        $m!($($p)* $($f)* __log_args_capture = $crate::arm_capture(&$scope, $ctx), $($message)+)
+    };
+
+    // === values ===
+    (@value $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] ?$v:expr $(, $($rest:tt)*)?) => {
+        $crate::__log_with_context!(@fields $scope, $m, $ctx, [$($p)*] [$($f)* ?$v,] $($($rest)*)?)
+    };
+    (@value $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] %$v:expr $(, $($rest:tt)*)?) => {
+        $crate::__log_with_context!(@fields $scope, $m, $ctx, [$($p)*] [$($f)* %$v,] $($($rest)*)?)
+    };
+    (@value $scope:ident, $m:path, $ctx:expr, [$($p:tt)*] [$($f:tt)*] $v:expr $(, $($rest:tt)*)?) => {
+        $crate::__log_with_context!(@fields $scope, $m, $ctx, [$($p)*] [$($f)* $v,] $($($rest)*)?)
+    };
+
+    // === entry ===
+    ($log_macro:path, $context:expr, [$($prefix:tt)*] $($args:tt)*) => {{
+        let __log_args_capture = $crate::CaptureScope::default();
+        $crate::__log_with_context!(@fields __log_args_capture, $log_macro, $context, [$($prefix)*] [] $($args)*);
+    }};
+}
+
 /// Global context-aware logging macros that inherit parent context
 /// These can be used in any function to automatically include context from parent functions with span
 #[macro_export]
 macro_rules! info {
     ($($t:tt)*) => {
-        $crate::log_with_context!(::tracing::info, $crate::get_context(), $($t)*);
+        $crate::log_with_context!(::tracing::info, $crate::get_inherited_context(), $($t)*)
     };
 }
 
 #[macro_export]
 macro_rules! warn {
     ($($t:tt)*) => {
-        $crate::log_with_context!(::tracing::warn, $crate::get_context(), $($t)*);
+        $crate::log_with_context!(::tracing::warn, $crate::get_inherited_context(), $($t)*)
     };
 }
 
 #[macro_export]
 macro_rules! error {
     ($($t:tt)*) => {
-        $crate::log_with_context!(::tracing::error, $crate::get_context(), $($t)*);
+        $crate::log_with_context!(::tracing::error, $crate::get_inherited_context(), $($t)*)
     };
 }
 
 #[macro_export]
 macro_rules! debug {
     ($($t:tt)*) => {
-        $crate::log_with_context!(::tracing::debug, $crate::get_context(), $($t)*);
+        $crate::log_with_context!(::tracing::debug, $crate::get_inherited_context(), $($t)*)
     };
 }
 
 #[macro_export]
 macro_rules! trace {
     ($($t:tt)*) => {
-        $crate::log_with_context!(::tracing::trace, $crate::get_context(), $($t)*);
+        $crate::log_with_context!(::tracing::trace, $crate::get_inherited_context(), $($t)*)
     };
 }
 
@@ -339,61 +613,37 @@ where
 }
 
 /// Get inherited context as a formatted string for automatic span propagation
-/// This function retrieves all context fields from the current span context
-/// and formats them as a string for logging
+/// Entries are sorted by key and escaped as described in [`format_context_string`];
+/// use [`parse_context_string`] to read the string back
 pub fn get_inherited_context_string() -> String {
-    let mut context_parts = Vec::new();
-    
-    // First, try to get context from tracing span (most reliable for cross-boundary propagation)
-    let current_span = tracing::Span::current();
-    if !current_span.is_none() {
-        // Try to extract fields from the current span
-        // This works across async boundaries when spans are properly propagated
-        // Note: Direct span field extraction is complex, so we rely on other methods
-    }
-    
-    // Try async context stack (most likely to have the context)
+    let mut context = HashMap::new();
+
+    // Async frames take precedence, innermost first
     if let Ok(stack) = ASYNC_CONTEXT_STACK.try_with(|stack| stack.borrow().clone()) {
-        // Search through all contexts in the stack, not just the most recent
         for context_map in stack.iter().rev() {
             for (key, value) in context_map {
-                // Skip function name to avoid duplication
-                if key != "function" && !context_parts.iter().any(|p: &String| p.starts_with(&format!("{}=", key))) {
-                    context_parts.push(format!("{}={}", key, value));
-                }
+                context.entry(key.clone()).or_insert_with(|| value.clone());
             }
         }
     }
-    
-    // Also try sync context stack and merge results
+
+    // Then the sync stack, for keys the async stack didn't have
     CONTEXT_STACK.with(|stack| {
-        let stack = stack.borrow();
-        for context_map in stack.iter().rev() {
+        for context_map in stack.borrow().iter().rev() {
             for (key, value) in context_map {
-                // Skip function name and avoid duplicates
-                if key != "function" && !context_parts.iter().any(|p: &String| p.starts_with(&format!("{}=", key))) {
-                    context_parts.push(format!("{}={}", key, value));
-                }
+                context.entry(key.clone()).or_insert_with(|| value.clone());
             }
         }
     });
-    
-    // If still no context, try global context store (for cross-boundary persistence)
-    if context_parts.is_empty() {
-        if let Some(global_context) = get_global_context() {
-            for (key, value) in global_context {
-                if key != "function" {
-                    context_parts.push(format!("{}={}", key, value));
-                }
-            }
-        }
-    }
-    
-    if context_parts.is_empty() {
-        "<no_context>".to_string()
-    } else {
-        context_parts.join(",")
+
+    // If still no context, fall back to the global context store (for cross-boundary persistence)
+    if context.is_empty() {
+        context = get_global_context().unwrap_or_default();
     }
+
+    // Skip function name to avoid duplication
+    context.remove("function");
+    format_context_string(&context)
 }
 
 /// Get inherited context fields as individual key-value pairs
@@ -0,0 +1,88 @@
+//! Bridge for the `log` crate facade.
+//!
+//! [`ContextLogger`] wraps an existing `log::Log` implementation and attaches the current
+//! runtime context to every record as `log` key-values, so dependencies and legacy modules
+//! that log through `log` get the same fields as `tracing` events:
+//!
+//! ```rust,ignore
+//! log_args_runtime::ContextLogger::new(env_logger::Logger::from_default_env())
+//!     .init(log::LevelFilter::Info)
+//!     .unwrap();
+//! ```
+//!
+//! Requires the `log` feature.
+
+use log::kv::{self, Key, Source, Value, VisitSource};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+use crate::get_merged_context;
+
+/// `log::Log` wrapper that adds the runtime context to each record's key-values
+pub struct ContextLogger<L> {
+    inner: L,
+}
+
+impl<L: Log> ContextLogger<L> {
+    /// Add the runtime context to the records passed to `inner`
+    pub fn new(inner: L) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped logger
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+
+    /// Install as the global `log` logger with the given maximum level
+    pub fn init(self, max_level: LevelFilter) -> Result<(), SetLoggerError>
+    where
+        L: 'static,
+    {
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+}
+
+impl<L: Log> Log for ContextLogger<L> {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let mut context: Vec<(String, String)> = get_merged_context().into_iter().collect();
+        if context.is_empty() {
+            return self.inner.log(record);
+        }
+        context.sort();
+
+        let source = ContextSource {
+            record: record.key_values(),
+            context,
+        };
+        self.inner
+            .log(&record.to_builder().key_values(&source).build());
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// The record's own key-values followed by context entries it doesn't already have
+struct ContextSource<'a> {
+    record: &'a dyn Source,
+    context: Vec<(String, String)>,
+}
+
+impl Source for ContextSource<'_> {
+    fn visit<'kvs>(&'kvs self, visitor: &mut dyn VisitSource<'kvs>) -> Result<(), kv::Error> {
+        self.record.visit(visitor)?;
+        for (key, value) in &self.context {
+            if self.record.get(Key::from_str(key)).is_none() {
+                visitor.visit_pair(Key::from_str(key), Value::from(value.as_str()))?;
+            }
+        }
+        Ok(())
+    }
+}
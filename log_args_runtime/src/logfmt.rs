@@ -0,0 +1,193 @@
+//! A logfmt event formatter for reading logs in a terminal.
+//!
+//! Each event becomes one line of `key=value` pairs: timestamp, level, target, message,
+//! function name, the event's own fields and then every inherited context key:
+//!
+//! ```text
+//! ts=2024-05-01T12:00:00.000000Z level=info target=billing msg="Charging card" function=charge_card amount=100 company_id=42
+//! ```
+//!
+//! ```rust,ignore
+//! tracing_subscriber::fmt()
+//!     .event_format(log_args_runtime::LogfmtFormat::new())
+//!     .init();
+//! ```
+
+use std::fmt;
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::get_merged_context;
+
+/// `FormatEvent` that writes events and their inherited context as logfmt
+#[derive(Debug, Clone)]
+pub struct LogfmtFormat {
+    timestamp: bool,
+    target: bool,
+}
+
+impl LogfmtFormat {
+    /// Create a formatter that includes the timestamp and target
+    pub fn new() -> Self {
+        Self {
+            timestamp: true,
+            target: true,
+        }
+    }
+
+    /// Whether to write the `ts` key
+    pub fn with_timestamp(mut self, timestamp: bool) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Whether to write the `target` key
+    pub fn with_target(mut self, target: bool) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl Default for LogfmtFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for LogfmtFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut visitor = LogfmtVisitor::default();
+        event.record(&mut visitor);
+        let mut context: Vec<(String, String)> = get_merged_context()
+            .into_iter()
+            .filter(|(key, _)| !visitor.fields.iter().any(|(name, _)| name == key))
+            .collect();
+        context.sort();
+
+        let mut line = Line::default();
+        if self.timestamp {
+            let mut timestamp = String::new();
+            SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
+            line.push("ts", &timestamp);
+        }
+        line.push("level", &event.metadata().level().as_str().to_lowercase());
+        if self.target {
+            line.push("target", event.metadata().target());
+        }
+        if let Some(message) = &visitor.message {
+            line.push("msg", message);
+        }
+
+        // The function name comes from the event, or else from the innermost decorated function
+        let function = take_field(&mut visitor.fields, "function")
+            .or_else(|| take_field(&mut context, "function"));
+        if let Some(function) = function {
+            line.push("function", &function);
+        }
+
+        for (key, value) in visitor.fields.iter().chain(&context) {
+            line.push(key, value);
+        }
+
+        writeln!(writer, "{}", line.0)
+    }
+}
+
+fn take_field(fields: &mut Vec<(String, String)>, name: &str) -> Option<String> {
+    let index = fields.iter().position(|(key, _)| key == name)?;
+    Some(fields.remove(index).1)
+}
+
+#[derive(Default)]
+struct Line(String);
+
+impl Line {
+    fn push(&mut self, key: &str, value: &str) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        push_key(&mut self.0, key);
+        self.0.push('=');
+        push_value(&mut self.0, value);
+    }
+}
+
+// Keys can't be quoted in logfmt, so characters that would end the key are replaced
+fn push_key(line: &mut String, key: &str) {
+    if key.is_empty() {
+        line.push('_');
+    }
+    line.extend(key.chars().map(|ch| {
+        if ch.is_whitespace() || ch.is_control() || ch == '=' || ch == '"' {
+            '_'
+        } else {
+            ch
+        }
+    }));
+}
+
+// Values are quoted when empty or when they contain spaces, `=`, quotes or control characters
+fn push_value(line: &mut String, value: &str) {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|ch| ch.is_whitespace() || ch.is_control() || ch == '=' || ch == '"' || ch == '\\');
+    if !needs_quotes {
+        line.push_str(value);
+        return;
+    }
+
+    line.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => line.push_str("\\\""),
+            '\\' => line.push_str("\\\\"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            '\t' => line.push_str("\\t"),
+            ch if ch.is_control() => line.push_str(&format!("\\u{{{:x}}}", ch as u32)),
+            ch => line.push(ch),
+        }
+    }
+    line.push('"');
+}
+
+#[derive(Default)]
+struct LogfmtVisitor {
+    message: Option<String>,
+    fields: Vec<(String, String)>,
+}
+
+impl LogfmtVisitor {
+    fn record(&mut self, field: &Field, value: String) {
+        if field.name() == "message" {
+            self.message = Some(value);
+        } else {
+            self.fields.push((field.name().to_string(), value));
+        }
+    }
+}
+
+impl Visit for LogfmtVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record(field, format!("{:?}", value));
+    }
+}
@@ -0,0 +1,101 @@
+//! Expansion of dotted field keys into nested JSON objects.
+//!
+//! `fields(person.contact.email)` logs the key `"person.contact.email"`. Log stores that map
+//! dots to objects reject such a flat key once `person` also exists as an object, so JSON
+//! output can expand it instead:
+//!
+//! ```text
+//! {"person.contact.email":"a@b.c"}  =>  {"person":{"contact":{"email":"a@b.c"}}}
+//! ```
+//!
+//! When a scalar and a dotted key share a prefix (`user` and `user.id`), the
+//! [`DottedKeyConflict`] policy decides what happens to them.
+
+use serde_json::{Map, Value};
+
+/// What to do when a dotted key runs into a scalar with the same prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DottedKeyConflict {
+    /// Turn the scalar into an object and keep it under `value`:
+    /// `{"user":{"value":"alice","id":7}}`
+    #[default]
+    MoveScalarToValue,
+    /// Leave the dotted key flat next to the scalar: `{"user":"alice","user.id":7}`
+    KeepDotted,
+    /// Leave the key flat with `_` in place of dots: `{"user":"alice","user_id":7}`
+    Underscore,
+}
+
+/// Expand every dotted key in `fields` into nested objects. Keys are processed in
+/// sorted order with undotted keys first, so the output doesn't depend on field order.
+pub(crate) fn nest_dotted_keys(
+    fields: Map<String, Value>,
+    conflict: DottedKeyConflict,
+) -> Map<String, Value> {
+    let (dotted, plain): (Vec<_>, Vec<_>) = fields
+        .into_iter()
+        .partition(|(key, _)| is_nestable(key));
+
+    let mut nested: Map<String, Value> = plain.into_iter().collect();
+    let mut dotted = dotted;
+    dotted.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let promote = conflict == DottedKeyConflict::MoveScalarToValue;
+    for (key, value) in dotted {
+        let Err(value) = insert_path(&mut nested, &key, value, promote) else {
+            continue;
+        };
+        let flat_key = match conflict {
+            DottedKeyConflict::Underscore => key.replace('.', "_"),
+            // The path is still taken, e.g. `user.value` after `user` was moved there
+            DottedKeyConflict::MoveScalarToValue | DottedKeyConflict::KeepDotted => key,
+        };
+        nested.entry(flat_key).or_insert(value);
+    }
+    nested
+}
+
+/// Insert `value` at a dotted path, creating objects along the way. A scalar in the way is
+/// moved under `value` when `promote` is set; otherwise, or if the path itself is already
+/// taken, the value is handed back.
+pub(crate) fn insert_path(
+    document: &mut Map<String, Value>,
+    path: &str,
+    value: Value,
+    promote: bool,
+) -> Result<(), Value> {
+    let mut segments = path.split('.').peekable();
+    let mut current = document;
+    while let Some(segment) = segments.next() {
+        if segments.peek().is_none() {
+            if current.contains_key(segment) {
+                return Err(value);
+            }
+            current.insert(segment.to_string(), value);
+            return Ok(());
+        }
+
+        let next = current
+            .entry(segment.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !next.is_object() {
+            if !promote {
+                return Err(value);
+            }
+            let scalar = next.take();
+            let mut object = Map::new();
+            object.insert("value".to_string(), scalar);
+            *next = Value::Object(object);
+        }
+        current = match next {
+            Value::Object(object) => object,
+            _ => return Err(value),
+        };
+    }
+    Err(value)
+}
+
+// `a.b` is expanded; `a`, `.a`, `a.` and `a..b` are kept as they are
+fn is_nestable(key: &str) -> bool {
+    key.contains('.') && key.split('.').all(|segment| !segment.is_empty())
+}
@@ -0,0 +1,139 @@
+//! Request id generation and extraction.
+//!
+//! `#[params(root)]` generates a request id for every request that doesn't bring its own;
+//! [`set_request_id_format`] picks how ids look. Generating ids needs the `request-id`
+//! feature. Ids that arrive with a request are read
+//! with [`request_id_from_headers`] and handed to `#[params(request_id = expr)]`:
+//!
+//! ```rust,ignore
+//! #[params(root, request_id = log_args_runtime::request_id_from_headers(&headers))]
+//! fn handle(headers: HashMap<String, String>) {
+//!     info!("Handling request"); // {"request_id": "<from the header, or a new id>"}
+//! }
+//! ```
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Header names checked by [`request_id_from_headers`], in order
+pub const REQUEST_ID_HEADERS: &[&str] = &["x-request-id", "x-correlation-id", "request-id"];
+
+/// Longest incoming request id that is accepted
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// How generated request ids look
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RequestIdFormat {
+    /// Random UUID, e.g. `5c8e4f0e-3b0a-4d5e-9b1f-2a6c7d8e9f01`
+    #[default]
+    UuidV4,
+    /// Time-ordered UUID, e.g. `01890a5d-ac96-774b-bcce-b302099a8057`
+    UuidV7,
+    /// Time-ordered ULID, e.g. `01ARZ3NDEKTSV4RRFFQ69G5FAV`
+    Ulid,
+}
+
+impl RequestIdFormat {
+    /// Generate a new id in this format
+    #[cfg(feature = "request-id")]
+    pub fn generate(self) -> String {
+        match self {
+            RequestIdFormat::UuidV4 => uuid::Uuid::new_v4().to_string(),
+            RequestIdFormat::UuidV7 => uuid::Uuid::now_v7().to_string(),
+            RequestIdFormat::Ulid => ulid::Ulid::new().to_string(),
+        }
+    }
+}
+
+static REQUEST_ID_FORMAT: AtomicU8 = AtomicU8::new(0);
+
+/// Set the format of request ids generated by `#[params(root)]`. Call this once at startup.
+pub fn set_request_id_format(format: RequestIdFormat) {
+    REQUEST_ID_FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+/// The format set with [`set_request_id_format`], UUIDv4 by default
+pub fn request_id_format() -> RequestIdFormat {
+    match REQUEST_ID_FORMAT.load(Ordering::Relaxed) {
+        1 => RequestIdFormat::UuidV7,
+        2 => RequestIdFormat::Ulid,
+        _ => RequestIdFormat::UuidV4,
+    }
+}
+
+/// Generate a request id in the configured format
+#[cfg(feature = "request-id")]
+pub fn new_request_id() -> String {
+    request_id_format().generate()
+}
+
+/// The first usable request id among the [`REQUEST_ID_HEADERS`], compared case-insensitively.
+/// Values are trimmed; empty values, values longer than 128 bytes and values with
+/// characters other than visible ASCII are skipped, so a client can't inject log lines.
+pub fn request_id_from_headers<I, K, V>(headers: I) -> Option<String>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut found: [Option<String>; REQUEST_ID_HEADERS.len()] = Default::default();
+    for (name, value) in headers {
+        let Some(index) = REQUEST_ID_HEADERS
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(name.as_ref().trim()))
+        else {
+            continue;
+        };
+        if found[index].is_none() {
+            found[index] = valid_request_id(value.as_ref());
+        }
+    }
+    found.into_iter().flatten().next()
+}
+
+/// Values accepted by `#[params(request_id = expr)]`. An absent or empty id is replaced
+/// with a generated one.
+pub trait IntoRequestId {
+    /// The id, if there is one
+    fn into_request_id(self) -> Option<String>;
+}
+
+impl IntoRequestId for String {
+    fn into_request_id(self) -> Option<String> {
+        Some(self)
+    }
+}
+
+impl IntoRequestId for &String {
+    fn into_request_id(self) -> Option<String> {
+        Some(self.clone())
+    }
+}
+
+impl IntoRequestId for &str {
+    fn into_request_id(self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl<T: IntoRequestId> IntoRequestId for Option<T> {
+    fn into_request_id(self) -> Option<String> {
+        self.and_then(IntoRequestId::into_request_id)
+    }
+}
+
+/// The id for `#[params(request_id = expr)]`, generating one if `id` is absent or empty
+#[cfg(feature = "request-id")]
+#[doc(hidden)]
+pub fn request_id_or_new(id: impl IntoRequestId) -> String {
+    id.into_request_id()
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(new_request_id)
+}
+
+fn valid_request_id(value: &str) -> Option<String> {
+    let value = value.trim();
+    let valid = !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value.chars().all(|ch| ch.is_ascii_graphic());
+    valid.then(|| value.to_string())
+}
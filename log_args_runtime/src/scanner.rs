@@ -0,0 +1,251 @@
+//! Optional scanning stage that masks secrets in rendered values.
+//!
+//! When enabled with [`set_sensitive_scanner`], every value pushed to the context
+//! stacks or recorded by `#[params]` is checked for credit-card numbers (Luhn-checked),
+//! JWTs, bearer tokens and AWS-style access keys before it reaches a subscriber.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+static SCANNER_ENABLED: AtomicBool = AtomicBool::new(false);
+
+static CREDIT_CARD_REDACTIONS: AtomicU64 = AtomicU64::new(0);
+static JWT_REDACTIONS: AtomicU64 = AtomicU64::new(0);
+static BEARER_TOKEN_REDACTIONS: AtomicU64 = AtomicU64::new(0);
+static AWS_KEY_REDACTIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of values masked by the scanner since startup or the last reset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RedactionCounts {
+    pub credit_cards: u64,
+    pub jwts: u64,
+    pub bearer_tokens: u64,
+    pub aws_keys: u64,
+}
+
+impl RedactionCounts {
+    /// Total redactions across all patterns
+    pub fn total(&self) -> u64 {
+        self.credit_cards + self.jwts + self.bearer_tokens + self.aws_keys
+    }
+}
+
+/// Turn the sensitive-data scanner on or off. It is off by default.
+pub fn set_sensitive_scanner(enabled: bool) {
+    SCANNER_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether the sensitive-data scanner is currently enabled
+pub fn sensitive_scanner_enabled() -> bool {
+    SCANNER_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Current redaction counters
+pub fn redaction_counts() -> RedactionCounts {
+    RedactionCounts {
+        credit_cards: CREDIT_CARD_REDACTIONS.load(Ordering::Relaxed),
+        jwts: JWT_REDACTIONS.load(Ordering::Relaxed),
+        bearer_tokens: BEARER_TOKEN_REDACTIONS.load(Ordering::Relaxed),
+        aws_keys: AWS_KEY_REDACTIONS.load(Ordering::Relaxed),
+    }
+}
+
+/// Reset all redaction counters to zero
+pub fn reset_redaction_counts() {
+    CREDIT_CARD_REDACTIONS.store(0, Ordering::Relaxed);
+    JWT_REDACTIONS.store(0, Ordering::Relaxed);
+    BEARER_TOKEN_REDACTIONS.store(0, Ordering::Relaxed);
+    AWS_KEY_REDACTIONS.store(0, Ordering::Relaxed);
+}
+
+/// Mask sensitive patterns in a value if the scanner is enabled,
+/// otherwise return it unchanged
+pub fn scan_value(value: &str) -> String {
+    if sensitive_scanner_enabled() {
+        redact_sensitive(value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Debug and Display wrapper used by `#[params]` so values are only rendered into an
+/// intermediate string when the scanner is enabled
+#[doc(hidden)]
+pub struct Scanned<T>(pub T);
+
+impl<T: fmt::Display> fmt::Display for Scanned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if sensitive_scanner_enabled() {
+            f.write_str(&redact_sensitive(&self.0.to_string()))
+        } else {
+            fmt::Display::fmt(&self.0, f)
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Scanned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if sensitive_scanner_enabled() {
+            f.write_str(&redact_sensitive(&format!("{:?}", self.0)))
+        } else {
+            fmt::Debug::fmt(&self.0, f)
+        }
+    }
+}
+
+fn redact_sensitive(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut result = String::with_capacity(value.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if is_word_start(bytes, i) {
+            if let Some((prefix, token)) = match_bearer_token(bytes, i) {
+                result.push_str(&value[i..i + prefix]);
+                result.push_str("[REDACTED:bearer_token]");
+                BEARER_TOKEN_REDACTIONS.fetch_add(1, Ordering::Relaxed);
+                i += prefix + token;
+                continue;
+            }
+            if let Some(len) = match_jwt(bytes, i) {
+                result.push_str("[REDACTED:jwt]");
+                JWT_REDACTIONS.fetch_add(1, Ordering::Relaxed);
+                i += len;
+                continue;
+            }
+            if let Some(len) = match_aws_key(bytes, i) {
+                result.push_str("[REDACTED:aws_key]");
+                AWS_KEY_REDACTIONS.fetch_add(1, Ordering::Relaxed);
+                i += len;
+                continue;
+            }
+            if let Some(len) = match_credit_card(bytes, i) {
+                result.push_str("[REDACTED:credit_card]");
+                CREDIT_CARD_REDACTIONS.fetch_add(1, Ordering::Relaxed);
+                i += len;
+                continue;
+            }
+        }
+
+        // Copy one (possibly multi-byte) character through unchanged
+        let ch = value[i..].chars().next().unwrap_or_default();
+        result.push(ch);
+        i += ch.len_utf8().max(1);
+    }
+
+    result
+}
+
+// Patterns only start where the previous character is not alphanumeric
+fn is_word_start(bytes: &[u8], i: usize) -> bool {
+    i == 0 || !bytes[i - 1].is_ascii_alphanumeric()
+}
+
+fn is_word_end(bytes: &[u8], i: usize) -> bool {
+    i >= bytes.len() || !bytes[i].is_ascii_alphanumeric()
+}
+
+fn is_base64url(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'_'
+}
+
+fn is_token_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~' | b'+' | b'/' | b'=')
+}
+
+fn run_length(bytes: &[u8], start: usize, pred: fn(u8) -> bool) -> usize {
+    bytes[start.min(bytes.len())..]
+        .iter()
+        .take_while(|b| pred(**b))
+        .count()
+}
+
+// `Bearer <token>`, case-insensitive; returns (prefix length, token length)
+fn match_bearer_token(bytes: &[u8], i: usize) -> Option<(usize, usize)> {
+    const PREFIX: &[u8] = b"bearer ";
+    let candidate = bytes.get(i..i + PREFIX.len())?;
+    if !candidate.eq_ignore_ascii_case(PREFIX) {
+        return None;
+    }
+    let token = run_length(bytes, i + PREFIX.len(), is_token_char);
+    (token >= 8).then_some((PREFIX.len(), token))
+}
+
+// `eyJ...` header, payload and signature segments separated by dots
+fn match_jwt(bytes: &[u8], i: usize) -> Option<usize> {
+    if !bytes[i..].starts_with(b"eyJ") {
+        return None;
+    }
+    let mut end = i;
+    for segment in 0..3 {
+        let len = run_length(bytes, end, is_base64url);
+        if len == 0 {
+            return None;
+        }
+        end += len;
+        if segment < 2 {
+            if bytes.get(end) != Some(&b'.') {
+                return None;
+            }
+            end += 1;
+        }
+    }
+    is_word_end(bytes, end).then_some(end - i)
+}
+
+// `AKIA`/`ASIA` followed by 16 uppercase letters or digits
+fn match_aws_key(bytes: &[u8], i: usize) -> Option<usize> {
+    if !(bytes[i..].starts_with(b"AKIA") || bytes[i..].starts_with(b"ASIA")) {
+        return None;
+    }
+    let body = bytes.get(i + 4..i + 20)?;
+    let valid = body
+        .iter()
+        .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit());
+    (valid && is_word_end(bytes, i + 20)).then_some(20)
+}
+
+// 13 to 19 digits, optionally grouped by single spaces or dashes, passing the Luhn check
+fn match_credit_card(bytes: &[u8], i: usize) -> Option<usize> {
+    let mut digits = Vec::new();
+    let mut end = i;
+    while end < bytes.len() {
+        let b = bytes[end];
+        if b.is_ascii_digit() {
+            digits.push(b - b'0');
+            end += 1;
+        } else if (b == b' ' || b == b'-')
+            && !digits.is_empty()
+            && bytes.get(end + 1).map_or(false, u8::is_ascii_digit)
+        {
+            end += 1;
+        } else {
+            break;
+        }
+    }
+
+    let valid = (13..=19).contains(&digits.len()) && is_word_end(bytes, end) && luhn_valid(&digits);
+    valid.then_some(end - i)
+}
+
+fn luhn_valid(digits: &[u8]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(index, &digit)| {
+            let digit = u32::from(digit);
+            if index % 2 == 1 {
+                let doubled = digit * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                digit
+            }
+        })
+        .sum();
+    sum % 10 == 0
+}
@@ -0,0 +1,66 @@
+//! Context boundaries for `#[params(root)]` and `#[params(isolate)]`.
+//!
+//! Context is thread-local, and custom fields are also kept in a process-wide store, so
+//! whatever one request leaves behind is inherited by the next one that runs on the same
+//! thread. A root scope drops the thread's context stacks and hides the global store
+//! until it ends. An isolated scope does the same, but puts the parent's stacks back when
+//! it ends, so a background job can run without the caller's context and the caller
+//! carries on as before.
+//!
+//! The guard must be dropped on the thread that created it, before any other code runs
+//! there, so the macro only allows these scopes on synchronous functions.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+
+use crate::{ASYNC_CONTEXT_STACK, CONTEXT_STACK};
+
+thread_local! {
+    static GLOBAL_CONTEXT_HIDDEN: Cell<bool> = const { Cell::new(false) };
+}
+
+type Stack = Vec<HashMap<String, String>>;
+
+/// Guard that ends a root or isolated scope on drop
+#[doc(hidden)]
+pub struct ScopeGuard {
+    // The parent's stacks, for isolated scopes
+    saved: Option<(Stack, Stack)>,
+    global_hidden: bool,
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        let (sync_stack, async_stack) = self.saved.take().unwrap_or_default();
+        CONTEXT_STACK.with(|stack| *stack.borrow_mut() = sync_stack);
+        let _ = ASYNC_CONTEXT_STACK.try_with(|stack| *stack.borrow_mut() = async_stack);
+        GLOBAL_CONTEXT_HIDDEN.with(|hidden| hidden.set(self.global_hidden));
+    }
+}
+
+/// Start a scope for a new request, discarding the context inherited so far
+#[doc(hidden)]
+pub fn enter_root_scope() -> ScopeGuard {
+    let mut guard = enter_isolated_scope();
+    guard.saved = None;
+    guard
+}
+
+/// Start a scope with an empty inherited context; the parent's is restored on drop
+#[doc(hidden)]
+pub fn enter_isolated_scope() -> ScopeGuard {
+    let sync_stack = CONTEXT_STACK.with(|stack| std::mem::take(&mut *stack.borrow_mut()));
+    let async_stack = ASYNC_CONTEXT_STACK
+        .try_with(|stack| std::mem::take(&mut *stack.borrow_mut()))
+        .unwrap_or_default();
+    let global_hidden = GLOBAL_CONTEXT_HIDDEN.with(|hidden| hidden.replace(true));
+    ScopeGuard {
+        saved: Some((sync_stack, async_stack)),
+        global_hidden,
+    }
+}
+
+/// Whether the global store is hidden because a root or isolated scope is active
+pub(crate) fn global_context_hidden() -> bool {
+    GLOBAL_CONTEXT_HIDDEN.with(Cell::get)
+}
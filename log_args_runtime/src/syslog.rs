@@ -0,0 +1,274 @@
+//! RFC 5424 syslog output.
+//!
+//! [`SyslogLayer`] writes one syslog message per event. The `function` key, the event's
+//! fields and the inherited context go into a single SD-ELEMENT:
+//!
+//! ```text
+//! <14>1 2024-05-01T12:00:00.000000Z api-1 billing 4242 - [logargs@32473 function="charge_card" amount="100" company_id="42"] Charging card
+//! ```
+//!
+//! Messages are newline-terminated by default. Use [`SyslogFraming::OctetCounting`] for TCP
+//! relays (RFC 6587) and [`SyslogFraming::None`] for UDP, where every datagram is one message.
+//! Each message is handed to the writer in a single `write_all`.
+//!
+//! ```rust,ignore
+//! use std::net::TcpStream;
+//! use std::sync::Mutex;
+//! use tracing_subscriber::prelude::*;
+//!
+//! let stream = TcpStream::connect("relay:601")?;
+//! let layer = log_args_runtime::SyslogLayer::new()
+//!     .with_app_name("billing")
+//!     .with_framing(log_args_runtime::SyslogFraming::OctetCounting)
+//!     .with_writer(Mutex::new(stream));
+//! tracing_subscriber::registry().with(layer).init();
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use serde_json::Value;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::get_merged_context;
+use crate::layer::event_fields;
+
+/// How syslog messages are delimited on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyslogFraming {
+    /// Each message is followed by `\n`
+    NewLine,
+    /// Each message is prefixed with its length in bytes and a space (RFC 6587)
+    OctetCounting,
+    /// No delimiter, for transports that keep message boundaries such as UDP
+    None,
+}
+
+/// Layer that writes events and the inherited context as RFC 5424 syslog messages
+pub struct SyslogLayer<W = fn() -> std::io::Stdout> {
+    make_writer: W,
+    facility: u8,
+    hostname: String,
+    app_name: String,
+    sd_id: String,
+    framing: SyslogFraming,
+}
+
+impl SyslogLayer {
+    /// Create a layer writing newline-terminated messages to stdout with the `user`
+    /// facility, the `HOSTNAME` environment variable as hostname, and the executable name
+    /// as app name
+    pub fn new() -> Self {
+        let app_name = std::env::current_exe().ok().and_then(|path| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        });
+        Self {
+            make_writer: std::io::stdout,
+            facility: 1,
+            hostname: header_value(default_hostname().as_deref(), 255),
+            app_name: header_value(app_name.as_deref(), 48),
+            sd_id: "logargs@32473".to_string(),
+            framing: SyslogFraming::NewLine,
+        }
+    }
+}
+
+impl Default for SyslogLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W> SyslogLayer<W> {
+    /// Write to a different destination, e.g. `Mutex<TcpStream>` or an in-memory buffer
+    pub fn with_writer<W2>(self, make_writer: W2) -> SyslogLayer<W2>
+    where
+        W2: for<'writer> MakeWriter<'writer> + 'static,
+    {
+        SyslogLayer {
+            make_writer,
+            facility: self.facility,
+            hostname: self.hostname,
+            app_name: self.app_name,
+            sd_id: self.sd_id,
+            framing: self.framing,
+        }
+    }
+
+    /// Facility code from 0 (`kern`) to 23 (`local7`); larger values are capped at 23
+    pub fn with_facility(mut self, facility: u8) -> Self {
+        self.facility = facility.min(23);
+        self
+    }
+
+    /// The HOSTNAME header field
+    pub fn with_hostname(mut self, hostname: &str) -> Self {
+        self.hostname = header_value(Some(hostname), 255);
+        self
+    }
+
+    /// The APP-NAME header field
+    pub fn with_app_name(mut self, app_name: &str) -> Self {
+        self.app_name = header_value(Some(app_name), 48);
+        self
+    }
+
+    /// SD-ID of the element holding the context, e.g. `myapp@12345` with your enterprise number
+    pub fn with_sd_id(mut self, sd_id: &str) -> Self {
+        self.sd_id = sd_name(sd_id);
+        self
+    }
+
+    /// How messages are delimited
+    pub fn with_framing(mut self, framing: SyslogFraming) -> Self {
+        self.framing = framing;
+        self
+    }
+}
+
+impl<S, W> Layer<S> for SyslogLayer<W>
+where
+    S: Subscriber,
+    W: for<'writer> MakeWriter<'writer> + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut fields = event_fields(event);
+        let mut context: BTreeMap<String, String> = get_merged_context()
+            .into_iter()
+            .filter(|(key, _)| !fields.contains_key(key))
+            .collect();
+        let text = fields.remove("message");
+
+        let mut timestamp = String::new();
+        if SystemTime
+            .format_time(&mut Writer::new(&mut timestamp))
+            .is_err()
+        {
+            timestamp = "-".to_string();
+        }
+
+        let mut line = format!(
+            "<{}>1 {} {} {} {} - ",
+            u16::from(self.facility) * 8 + u16::from(severity(metadata.level())),
+            timestamp,
+            self.hostname,
+            self.app_name,
+            std::process::id(),
+        );
+
+        // The function name comes from the event, or else from the innermost decorated function
+        let mut params: Vec<(String, String)> = Vec::new();
+        let function = fields
+            .remove("function")
+            .map(|value| value_string(&value))
+            .or_else(|| context.remove("function"));
+        if let Some(function) = function {
+            params.push(("function".to_string(), function));
+        }
+        params.extend(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), value_string(value))),
+        );
+        params.extend(context);
+
+        if params.is_empty() {
+            line.push('-');
+        } else {
+            line.push('[');
+            line.push_str(&self.sd_id);
+            for (key, value) in &params {
+                line.push(' ');
+                line.push_str(&sd_name(key));
+                line.push_str("=\"");
+                for ch in value.chars() {
+                    if matches!(ch, '"' | '\\' | ']') {
+                        line.push('\\');
+                    }
+                    line.push(ch);
+                }
+                line.push('"');
+            }
+            line.push(']');
+        }
+
+        if let Some(text) = text {
+            line.push(' ');
+            line.push_str(&value_string(&text));
+        }
+
+        let encoded = match self.framing {
+            SyslogFraming::NewLine => format!("{}\n", line),
+            SyslogFraming::OctetCounting => format!("{} {}", line.len(), line),
+            SyslogFraming::None => line,
+        };
+        let mut writer = self.make_writer.make_writer_for(metadata);
+        let _ = writer.write_all(encoded.as_bytes());
+    }
+}
+
+/// Syslog severity of a tracing level, shared with the GELF `level` field
+pub(crate) fn severity(level: &Level) -> u8 {
+    match *level {
+        Level::ERROR => 3,
+        Level::WARN => 4,
+        Level::INFO => 6,
+        Level::DEBUG | Level::TRACE => 7,
+    }
+}
+
+/// Host name from the `HOSTNAME` environment variable
+pub(crate) fn default_hostname() -> Option<String> {
+    std::env::var("HOSTNAME")
+        .ok()
+        .filter(|hostname| !hostname.is_empty())
+}
+
+fn value_string(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+// Header fields are printable ASCII without spaces, or `-` when unknown
+fn header_value(value: Option<&str>, max_len: usize) -> String {
+    let value: String = value
+        .unwrap_or_default()
+        .chars()
+        .filter(|ch| ch.is_ascii_graphic())
+        .take(max_len)
+        .collect();
+    if value.is_empty() {
+        "-".to_string()
+    } else {
+        value
+    }
+}
+
+// SD-IDs and PARAM-NAMEs are at most 32 printable ASCII characters other than `=`, `]`,
+// `"` and space
+fn sd_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_graphic() && !matches!(ch, '=' | ']' | '"') {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .take(32)
+        .collect();
+    if name.is_empty() {
+        "_".to_string()
+    } else {
+        name
+    }
+}
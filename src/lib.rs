@@ -53,6 +53,10 @@
 //!
 //! See the [USAGE.md](https://github.com/MKJSM/log-args/blob/main/USAGE.md) for comprehensive documentation.
 
+mod policy;
+mod rewrite;
+
+use policy::Policy;
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::parse::{Parse, Parser};
@@ -150,6 +154,122 @@ use syn::{parenthesized, Expr, FnArg, Ident, MetaNameValue, Pat, Token};
 /// This is useful for debugging or when you want to ensure all parameters are logged
 /// regardless of other attributes.
 ///
+/// ## Type-Based Exclusion
+///
+/// Parameters whose type is `Secret`, `Password`, `Token` or `Credentials` are never
+/// logged by `all`. Types are matched on the last path segment, looking through
+/// references and generics, so `&Token` and `Arc<Credentials>` are skipped as well.
+/// Use `skip_types(...)` to replace this list with your own:
+///
+/// ```rust,ignore
+/// #[params(all, skip_types(PgPool, AppState))]
+/// fn handle_request(pool: &PgPool, state: Arc<AppState>, user_id: u64) {
+///     info!("Handling request"); // Only user_id is logged
+/// }
+/// ```
+///
+/// ## Sensitive Parameter Warnings
+///
+/// With `all`, parameters whose name or type contains `password`, `secret`, `token`,
+/// `api_key`, `ssn` or `card` produce a compile-time warning naming the parameter.
+/// Acknowledge each one with `skip(...)`, `redact(...)` (logged as `"[REDACTED]"`) or
+/// `allow_sensitive(...)`; a bare `allow_sensitive` silences the whole function.
+/// `strict` turns the warnings into errors and `sensitive_patterns("pin", "cvv")`
+/// replaces the pattern list:
+///
+/// ```rust,ignore
+/// #[params(all, strict, skip(session_token), redact(password))]
+/// fn login(username: String, password: String, session_token: String) {
+///     info!("Login attempt");
+/// }
+/// ```
+///
+/// ## Hashed Fields
+///
+/// `hash(...)` logs a keyed HMAC-SHA256 (truncated to 32 hex characters) instead of the
+/// raw value, so a user can be correlated across logs without storing their email.
+/// The hash replaces the value both in the emitted fields and in the propagated context.
+/// Supply the key once at startup:
+///
+/// ```rust,ignore
+/// log_args_runtime::init_hash_key(b"load-me-from-a-secret-store");
+///
+/// #[params(fields(user.id), hash(user.email, ip))]
+/// fn track_login(user: User, ip: String) {
+///     info!("Login tracked");
+/// }
+/// ```
+///
+/// Until a key is set, hashed fields are logged as `<hash_key_unset>`. Hashing needs the
+/// `hash` feature of `log-args-runtime`; without it, `hash(...)` is a compile error.
+///
+/// ## Project Policy
+///
+/// A `log_args.toml` at the crate root can allow or deny field keys, replace the
+/// sensitive patterns and `skip_types` defaults, pick the function name and key casing
+/// and supply default attributes for every `#[params]` in the crate. Fields that violate
+/// the policy are compile errors; see `USAGE.md` for the file format.
+///
+/// ## Key Styles and Prefixes
+///
+/// `key_style = "snake"` or `"camel"` and `prefix = "..."` rename every emitted field and
+/// propagated context key:
+///
+/// ```rust,ignore
+/// #[params(fields(user.id), key_style = "camel", prefix = "args")]
+/// fn load(user: User) {
+///     info!("Loading"); // field `argsUserId`; with "snake" it is `args.user_id`
+/// }
+/// ```
+///
+/// The project default lives in the `[keys]` section of `log_args.toml`.
+///
+/// ## Qualified and Aliased Log Macros
+///
+/// Besides bare `info!`/`warn!`/..., calls written as `tracing::info!`, `log::warn!`,
+/// `tracing::event!(Level::INFO, ...)` or through an alias imported inside the body
+/// (`use tracing::info as log_info;`) also carry the configured fields:
+///
+/// ```rust,ignore
+/// #[params(fields(order_id))]
+/// fn ship(order_id: u64) {
+///     tracing::warn!(target: "shipping", "Carrier delayed");
+///     log::info!(carrier = "ups"; "Label printed");
+/// }
+/// ```
+///
+/// `log` calls are emitted as `tracing` events. Aliases imported outside the function
+/// can't be seen by the macro and are left unchanged, and so is a bare `event!` unless
+/// the body imports it from `tracing`.
+///
+/// ## Custom Logging Macros
+///
+/// List your own logging macros in `macros(...)` and they receive the configured fields as
+/// leading arguments, plus the inherited context, just like the built-in macros:
+///
+/// ```rust,ignore
+/// #[params(fields(order_id), macros(audit, metric))]
+/// fn approve(order_id: u64) {
+///     audit!("Order approved"); // expands to audit!(order_id = ..., "Order approved")
+/// }
+/// ```
+///
+/// ## `log` Crate Compatibility
+///
+/// With `log_compat`, bare `info!`/`warn!`/... and `log::` calls take `log` syntax and go
+/// through the `log` facade, with the configured fields attached as key-values. This needs
+/// the `log` feature of `log-args-runtime`; without it, each call is a compile error:
+///
+/// ```rust,ignore
+/// #[params(fields(order_id), log_compat)]
+/// fn ship(order_id: u64) {
+///     info!(carrier = "ups"; "Label printed"); // key-values: order_id, carrier
+/// }
+/// ```
+///
+/// Install `log_args_runtime::ContextLogger` around your `log` logger so inherited context
+/// is attached as well.
+///
 /// ## Span Context Propagation (Enabled by Default)
 ///
 /// **Note: Span propagation is now enabled by default with `#[params]`.**
@@ -172,21 +292,66 @@ use syn::{parenthesized, Expr, FnArg, Ident, MetaNameValue, Pat, Token};
 /// }
 /// ```
 ///
+/// `root` marks where a new request starts: context left on the thread by earlier requests
+/// is dropped and a `request_id` is generated unless the function logs one. `isolate` runs
+/// a function, such as a background job, without the caller's context and restores it
+/// afterwards. Both are rejected on `async fn`s. `request_id = expr` takes the id from an expression instead, for example
+/// `log_args_runtime::request_id_from_headers(&headers)`, and generates one when it is absent.
+///
+/// With `call_id`, every invocation gets its own id and children see it as
+/// `parent_call_id`, so call trees can be rebuilt from the logs.
+/// `call_path` adds the chain of decorated functions that led to a log
+/// (`HandleRequest>ValidateUser>LoadProfile`) and its `depth`; `call_path(max_len = N)`
+/// shortens longer paths to their innermost `N` functions.
+///
 /// ## Function Name Logging
 ///
-/// Enable function name logging with Cargo features:
+/// Enable function name logging with the `function-names` Cargo feature:
 ///
 /// ```toml
 /// [dependencies]
-/// log_args = { version = "0.1", features = ["function-names-pascal"] }
+/// log_args = { version = "0.1", features = ["function-names"] }
 /// ```
 ///
-/// Available casing styles:
-/// - `function-names-snake` → `process_payment`
-/// - `function-names-camel` → `processPayment`
-/// - `function-names-pascal` → `ProcessPayment` (recommended)
-/// - `function-names-screaming` → `PROCESS_PAYMENT`
-/// - `function-names-kebab` → `process-payment`
+/// Names are PascalCase unless `[function_names] style` in `log_args.toml` or a
+/// function's `name_style = "..."` picks another casing:
+/// - `"snake"` → `process_payment`
+/// - `"camel"` → `processPayment`
+/// - `"pascal"` → `ProcessPayment`
+/// - `"screaming"` → `PROCESS_PAYMENT`
+/// - `"kebab"` → `process-payment`
+///
+/// `name_style` also turns the field on without the feature, and `name_style = "none"`
+/// turns it off for one function. Acronyms and digits stay in one word (`parseHTTPRequest`
+/// → `parse_http_request`, `get_v2_user` → `getV2User`), leading underscores are kept and
+/// raw identifiers lose their `r#`. The `function-names-<style>` features are deprecated.
+///
+/// `name = "..."` replaces the logged name, and `qualified` prefixes methods with their
+/// self type. Put `#[params(...)]` on the impl block to decorate every method and include
+/// the trait as well:
+///
+/// ```rust,ignore
+/// #[params(name = "billing.charge")]
+/// fn charge(amount: u64) {} // function = "billing.charge"
+///
+/// #[params(qualified)]
+/// impl Handler for UserService {
+///     fn handle(&self) {} // function = "<UserService as Handler>::handle"
+/// }
+/// ```
+///
+/// ## Source Location
+///
+/// `location` adds the `location.module_path`, `location.file` and `location.line` of the
+/// function definition to every log and to the propagated context. `location(call_site)` also adds the `call_file` and
+/// `call_line` of each log macro:
+///
+/// ```rust,ignore
+/// #[params(location(call_site))]
+/// fn process(id: u64) {
+///     info!("Processing"); // location.module_path, location.file, location.line, call_file, call_line
+/// }
+/// ```
 ///
 /// ## Async Support
 ///
@@ -260,29 +425,193 @@ use syn::{parenthesized, Expr, FnArg, Ident, MetaNameValue, Pat, Token};
 ///
 #[proc_macro_attribute]
 pub fn params(args: TokenStream, input: TokenStream) -> TokenStream {
-    let mut item = if let Ok(item_fn) = syn::parse::<syn::ItemFn>(input.clone()) {
+    let item = if let Ok(item_fn) = syn::parse::<syn::ItemFn>(input.clone()) {
         FnItem::Item(item_fn)
     } else if let Ok(impl_item_fn) = syn::parse::<syn::ImplItemFn>(input.clone()) {
         FnItem::ImplItem(impl_item_fn)
+    } else if let Ok(item_impl) = syn::parse::<syn::ItemImpl>(input.clone()) {
+        return expand_impl(args.into(), item_impl).into();
     } else {
         return syn::Error::new_spanned(
             proc_macro2::TokenStream::from(input),
-            "The #[params] attribute can only be applied to functions or methods.",
+            "The #[params] attribute can only be applied to functions, methods or impl blocks.",
         )
         .to_compile_error()
         .into();
     };
 
+    expand_params(args.into(), item, None).into()
+}
+
+/// Self type and trait of the impl block a method is expanded from
+#[derive(Clone)]
+struct ImplContext {
+    self_ty: String,
+    trait_name: Option<String>,
+}
+
+impl ImplContext {
+    fn new(item_impl: &syn::ItemImpl) -> Self {
+        // Generic arguments and module paths are left out: `crate::Repo<T>` is `Repo`
+        let self_ty = match &*item_impl.self_ty {
+            syn::Type::Path(type_path) => type_path
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string()),
+            _ => None,
+        };
+        let self_ty = self_ty.unwrap_or_else(|| {
+            let self_ty = &item_impl.self_ty;
+            quote!(#self_ty).to_string().replace(' ', "")
+        });
+        let trait_name = item_impl.trait_.as_ref().and_then(|(_, path, _)| {
+            path.segments.last().map(|segment| segment.ident.to_string())
+        });
+        Self { self_ty, trait_name }
+    }
+}
+
+// `#[params(...)]` on an impl block decorates every method in it. A method's own
+// `#[params(...)]` attributes are added after the impl-level ones.
+fn expand_impl(
+    args: proc_macro2::TokenStream,
+    mut item_impl: syn::ItemImpl,
+) -> proc_macro2::TokenStream {
+    let impl_context = ImplContext::new(&item_impl);
+    let mut method_args = Vec::new();
+    for impl_item in &mut item_impl.items {
+        let syn::ImplItem::Fn(method) = impl_item else {
+            continue;
+        };
+
+        let mut args = args.clone();
+        let mut attrs = Vec::new();
+        for attr in std::mem::take(&mut method.attrs) {
+            let is_params = attr
+                .path()
+                .segments
+                .last()
+                .map_or(false, |segment| segment.ident == "params");
+            if !is_params {
+                attrs.push(attr);
+                continue;
+            }
+            if let syn::Meta::List(list) = &attr.meta {
+                if !args.is_empty() && !list.tokens.is_empty() {
+                    args.extend(quote! { , });
+                }
+                args.extend(list.tokens.clone());
+            }
+        }
+        method.attrs = attrs;
+        method_args.push(args);
+    }
+
+    // Without its `#[params]` attributes, in case any method fails to expand
+    let unexpanded = item_impl.clone();
+    let mut errors = proc_macro2::TokenStream::new();
+    let methods = item_impl
+        .items
+        .iter_mut()
+        .filter(|impl_item| matches!(impl_item, syn::ImplItem::Fn(_)));
+    for (impl_item, args) in methods.zip(method_args) {
+        let syn::ImplItem::Fn(method) = impl_item else {
+            continue;
+        };
+        let expanded = expand_params(
+            args,
+            FnItem::ImplItem(method.clone()),
+            Some(&impl_context),
+        );
+        match syn::parse2::<syn::ImplItem>(expanded.clone()) {
+            Ok(expanded_item @ syn::ImplItem::Fn(_)) => *impl_item = expanded_item,
+            // Anything else is a `compile_error!`, reported as it is
+            _ => errors.extend(expanded),
+        }
+    }
+    if !errors.is_empty() {
+        // The impl is kept so the errors aren't followed by missing-method errors
+        return quote! { #errors #unexpanded };
+    }
+    quote! { #item_impl }
+}
+
+fn expand_params(
+    args: proc_macro2::TokenStream,
+    mut item: FnItem,
+    impl_context: Option<&ImplContext>,
+) -> proc_macro2::TokenStream {
     let allow_unused_macros_attr: syn::Attribute = syn::parse_quote! { #[allow(unused_macros)] };
     item.attrs_mut().push(allow_unused_macros_attr);
 
-    let attrs = match Punctuated::<Attribute, Token![,]>::parse_terminated.parse(args) {
-        Ok(attrs) => attrs,
-        Err(e) => return e.to_compile_error().into(),
+    let policy = match Policy::load() {
+        Ok(policy) => policy,
+        Err(message) => {
+            return syn::Error::new(proc_macro2::Span::call_site(), message)
+                .to_compile_error()
+        }
+    };
+
+    // Project-wide default attributes come first so the function's own attributes extend them
+    let mut attrs = Punctuated::<Attribute, Token![,]>::new();
+    for default_attr in &policy.default_attributes {
+        match syn::parse_str::<Attribute>(default_attr) {
+            Ok(attr) => attrs.push(attr),
+            Err(e) => {
+                return syn::Error::new(
+                    proc_macro2::Span::call_site(),
+                    format!("invalid default attribute \"{}\" in log_args.toml: {}", default_attr, e),
+                )
+                .to_compile_error()
+            }
+        }
+    }
+    match Punctuated::<Attribute, Token![,]>::parse_terminated.parse2(args) {
+        Ok(fn_attrs) => attrs.extend(fn_attrs),
+        Err(e) => return e.to_compile_error(),
     };
 
-    let config = AttrConfig::from_attributes(attrs);
-    let context_fields = get_context_fields_quote(&item, &config);
+    let mut config = AttrConfig::from_attributes(attrs, &policy);
+    config.impl_context = impl_context.cloned();
+    if let Err(e) = check_policy(&item, &config, &policy) {
+        return e.to_compile_error();
+    }
+    if let Err(e) = check_request_id(&item, &config) {
+        return e.to_compile_error();
+    }
+    if let Err(e) = check_scope(&item, &config) {
+        return e.to_compile_error();
+    }
+    let sensitive_warnings = match get_sensitive_warnings(&item, &config) {
+        Ok(warnings) => warnings,
+        Err(e) => return e.to_compile_error(),
+    };
+    // Make Cargo rebuild this crate when the policy file changes
+    let policy_tracking = policy.path.as_ref().map(|path| {
+        let path = path.display().to_string();
+        quote! { const _: &[u8] = include_bytes!(#path); }
+    });
+    let current_field_locals = get_current_field_locals(&config);
+    let function_name_local = get_function_name_local(&item, &config);
+    let location_local = get_location_local(&item, &config);
+    let scope_locals = get_scope_locals(&item, &config);
+    let call_id_locals = get_call_id_locals(&config);
+    let call_path_local = get_call_path_local(&item, &config);
+    let block_prelude = quote! {
+        #policy_tracking
+        #sensitive_warnings
+        #current_field_locals
+        #function_name_local
+        #location_local
+        #scope_locals
+        #call_id_locals
+        #call_path_local
+    };
+    let mut context_fields = get_context_fields_quote(&item, &config);
+    rewrite::rewrite_log_macros(item.block_mut(), &context_fields, &config);
+    // Inside the shadowing `macro_rules!`, call-site spans resolve to each invocation
+    context_fields.extend(call_site_fields(&config, proc_macro2::Span::call_site()));
 
     if config.span {
         // Generate context map for span propagation
@@ -293,6 +622,7 @@ pub fn params(args: TokenStream, input: TokenStream) -> TokenStream {
             let original_block = item.block();
             let new_block = quote! {
                 {
+                    #block_prelude
                     let _context_guard = ::log_args_runtime::push_async_context(#context_map);
                     #log_redefines
                     #original_block
@@ -304,6 +634,7 @@ pub fn params(args: TokenStream, input: TokenStream) -> TokenStream {
             let original_block = item.block();
             let new_block = quote! {
                 {
+                    #block_prelude
                     let _context_guard = ::log_args_runtime::push_context(#context_map);
                     #log_redefines
                     #original_block
@@ -318,6 +649,7 @@ pub fn params(args: TokenStream, input: TokenStream) -> TokenStream {
             let original_block = item.block();
             let new_block = quote! {
                 {
+                    #block_prelude
                     #log_redefines
                     #original_block
                 }
@@ -328,6 +660,7 @@ pub fn params(args: TokenStream, input: TokenStream) -> TokenStream {
             let original_block = item.block();
             let new_block = quote! {
                 {
+                    #block_prelude
                     #log_redefines
                     #original_block
                 }
@@ -336,7 +669,7 @@ pub fn params(args: TokenStream, input: TokenStream) -> TokenStream {
         }
     }
 
-    TokenStream::from(quote! { #item })
+    quote! { #item }
 }
 
 enum Attribute {
@@ -347,6 +680,26 @@ enum Attribute {
     Span,
     All,
     AutoCapture,  // New attribute for automatic closure context capture
+    SkipTypes(Punctuated<Ident, Token![,]>),
+    Skip(Punctuated<Ident, Token![,]>),
+    Redact(Punctuated<Ident, Token![,]>),
+    AllowSensitive(Punctuated<Ident, Token![,]>),
+    SensitivePatterns(Punctuated<syn::LitStr, Token![,]>),
+    Strict,
+    Hash(Punctuated<Expr, Token![,]>),
+    Macros(Punctuated<syn::Path, Token![,]>),
+    LogCompat,
+    KeyStyle(KeyStyle),
+    Prefix(String),
+    Name(String),
+    Qualified,
+    NameStyle(Option<NameStyle>),
+    Location { call_site: bool },
+    CallId,
+    CallPath { max_len: Option<usize> },
+    Root,
+    Isolate,
+    RequestId(Expr),
 }
 
 impl Parse for Attribute {
@@ -375,6 +728,123 @@ impl Parse for Attribute {
             Ok(Attribute::All)
         } else if ident == "auto_capture" {
             Ok(Attribute::AutoCapture)
+        } else if ident == "skip_types" {
+            let content;
+            parenthesized!(content in input);
+            let skip_types = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+            Ok(Attribute::SkipTypes(skip_types))
+        } else if ident == "skip" {
+            let content;
+            parenthesized!(content in input);
+            let skip = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+            Ok(Attribute::Skip(skip))
+        } else if ident == "redact" {
+            let content;
+            parenthesized!(content in input);
+            let redact = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+            Ok(Attribute::Redact(redact))
+        } else if ident == "allow_sensitive" {
+            // Bare `allow_sensitive` acknowledges every parameter
+            let mut allowed = Punctuated::new();
+            if input.peek(syn::token::Paren) {
+                let content;
+                parenthesized!(content in input);
+                allowed = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+            }
+            Ok(Attribute::AllowSensitive(allowed))
+        } else if ident == "sensitive_patterns" {
+            let content;
+            parenthesized!(content in input);
+            let patterns = Punctuated::<syn::LitStr, Token![,]>::parse_terminated(&content)?;
+            Ok(Attribute::SensitivePatterns(patterns))
+        } else if ident == "strict" {
+            Ok(Attribute::Strict)
+        } else if ident == "hash" {
+            let content;
+            parenthesized!(content in input);
+            let hash = Punctuated::<Expr, Token![,]>::parse_terminated(&content)?;
+            Ok(Attribute::Hash(hash))
+        } else if ident == "macros" {
+            let content;
+            parenthesized!(content in input);
+            let macros = Punctuated::<syn::Path, Token![,]>::parse_terminated(&content)?;
+            Ok(Attribute::Macros(macros))
+        } else if ident == "log_compat" {
+            Ok(Attribute::LogCompat)
+        } else if ident == "key_style" {
+            input.parse::<Token![=]>()?;
+            let style: syn::LitStr = input.parse()?;
+            let key_style = KeyStyle::parse(&style.value()).ok_or_else(|| {
+                syn::Error::new_spanned(&style, "unknown key style (expected \"snake\" or \"camel\")")
+            })?;
+            Ok(Attribute::KeyStyle(key_style))
+        } else if ident == "prefix" {
+            input.parse::<Token![=]>()?;
+            let prefix: syn::LitStr = input.parse()?;
+            Ok(Attribute::Prefix(prefix.value()))
+        } else if ident == "name" {
+            input.parse::<Token![=]>()?;
+            let name: syn::LitStr = input.parse()?;
+            if name.value().is_empty() {
+                return Err(syn::Error::new_spanned(name, "function name must not be empty"));
+            }
+            Ok(Attribute::Name(name.value()))
+        } else if ident == "qualified" {
+            Ok(Attribute::Qualified)
+        } else if ident == "call_id" {
+            Ok(Attribute::CallId)
+        } else if ident == "request_id" {
+            input.parse::<Token![=]>()?;
+            Ok(Attribute::RequestId(input.parse()?))
+        } else if ident == "root" {
+            Ok(Attribute::Root)
+        } else if ident == "isolate" {
+            Ok(Attribute::Isolate)
+        } else if ident == "call_path" {
+            // `call_path(max_len = N)` limits how many functions the path shows
+            let mut max_len = None;
+            if input.peek(syn::token::Paren) {
+                let content;
+                parenthesized!(content in input);
+                let option: Ident = content.parse()?;
+                if option != "max_len" {
+                    return Err(syn::Error::new_spanned(option, "expected `max_len`"));
+                }
+                content.parse::<Token![=]>()?;
+                let value: syn::LitInt = content.parse()?;
+                let len: usize = value.base10_parse()?;
+                if len == 0 {
+                    return Err(syn::Error::new_spanned(value, "`max_len` must be at least 1"));
+                }
+                max_len = Some(len);
+            }
+            Ok(Attribute::CallPath { max_len })
+        } else if ident == "location" {
+            // `location(call_site)` also records where each log macro is invoked
+            let mut call_site = false;
+            if input.peek(syn::token::Paren) {
+                let content;
+                parenthesized!(content in input);
+                let option: Ident = content.parse()?;
+                if option != "call_site" {
+                    return Err(syn::Error::new_spanned(option, "expected `call_site`"));
+                }
+                call_site = true;
+            }
+            Ok(Attribute::Location { call_site })
+        } else if ident == "name_style" {
+            input.parse::<Token![=]>()?;
+            let style: syn::LitStr = input.parse()?;
+            if style.value() == "none" {
+                return Ok(Attribute::NameStyle(None));
+            }
+            let name_style = NameStyle::parse(&style.value()).ok_or_else(|| {
+                syn::Error::new_spanned(
+                    &style,
+                    "unknown name style (expected \"snake\", \"camel\", \"pascal\", \"screaming\", \"kebab\" or \"none\")",
+                )
+            })?;
+            Ok(Attribute::NameStyle(Some(name_style)))
         } else {
             Err(syn::Error::new_spanned(ident, "unknown attribute"))
         }
@@ -389,8 +859,43 @@ struct AttrConfig {
     span: bool,
     all_params: bool,
     auto_capture: bool,  // New field for automatic closure context capture
+    skip_types: Vec<String>,
+    skip: Vec<Ident>,
+    redact: Vec<Ident>,
+    allow_sensitive: Vec<Ident>,
+    allow_all_sensitive: bool,
+    sensitive_patterns: Vec<String>,
+    strict: bool,
+    hash: Vec<syn::Expr>,
+    name_style: Option<NameStyle>,
+    macros: Vec<syn::Path>,
+    log_compat: bool,
+    key_style: Option<KeyStyle>,
+    key_prefix: String,
+    function_name: Option<String>,
+    qualified: bool,
+    impl_context: Option<ImplContext>,
+    location: bool,
+    call_site_location: bool,
+    call_id: bool,
+    call_path: Option<usize>,
+    root: bool,
+    isolate: bool,
+    request_id: Option<Expr>,
 }
 
+/// Parameter types that `all` never logs unless `skip_types(...)` overrides the list.
+const DEFAULT_SKIP_TYPES: &[&str] = &["Secret", "Password", "Token", "Credentials"];
+
+/// Placeholder logged in place of parameters listed in `redact(...)`.
+const REDACTED_VALUE: &str = "[REDACTED]";
+
+/// Functions shown in a `call_path` unless `call_path(max_len = N)` sets another limit.
+const DEFAULT_CALL_PATH_LEN: usize = 8;
+
+/// Name fragments that make `all` warn about a parameter unless `sensitive_patterns(...)` overrides them.
+const DEFAULT_SENSITIVE_PATTERNS: &[&str] = &["password", "secret", "token", "api_key", "ssn", "card"];
+
 impl Default for AttrConfig {
     fn default() -> Self {
         Self {
@@ -401,13 +906,51 @@ impl Default for AttrConfig {
             span: true,          // Default to true for context propagation
             all_params: false,
             auto_capture: false, // Default to false for auto_capture
+            skip_types: DEFAULT_SKIP_TYPES.iter().map(|t| t.to_string()).collect(),
+            skip: Vec::new(),
+            redact: Vec::new(),
+            allow_sensitive: Vec::new(),
+            allow_all_sensitive: false,
+            sensitive_patterns: DEFAULT_SENSITIVE_PATTERNS.iter().map(|p| p.to_string()).collect(),
+            strict: false,
+            hash: Vec::new(),
+            name_style: NameStyle::from_features(),
+            macros: Vec::new(),
+            log_compat: false,
+            key_style: None,
+            key_prefix: String::new(),
+            function_name: None,
+            qualified: false,
+            impl_context: None,
+            location: false,
+            call_site_location: false,
+            call_id: false,
+            call_path: None,
+            root: false,
+            isolate: false,
+            request_id: None,
         }
     }
 }
 
 impl AttrConfig {
-    fn from_attributes(attrs: Punctuated<Attribute, Token![,]>) -> Self {
+    fn from_attributes(attrs: Punctuated<Attribute, Token![,]>, policy: &Policy) -> Self {
         let mut config = AttrConfig::default();
+        if let Some(skip_types) = &policy.skip_types {
+            config.skip_types = skip_types.clone();
+        }
+        if let Some(patterns) = &policy.sensitive_patterns {
+            config.sensitive_patterns = patterns.clone();
+        }
+        if policy.name_style.is_some() {
+            config.name_style = policy.name_style;
+        }
+        config.key_style = policy.key_style;
+        if let Some(prefix) = &policy.key_prefix {
+            config.key_prefix = prefix.clone();
+        }
+        let mut skip_types_overridden = false;
+        let mut sensitive_patterns_overridden = false;
         for attr in attrs {
             match attr {
                 Attribute::Fields(fields) => config.fields.extend(fields),
@@ -424,10 +967,118 @@ impl AttrConfig {
                 Attribute::AutoCapture => {
                     config.auto_capture = true;
                 }
+                Attribute::SkipTypes(skip_types) => {
+                    // An explicit list replaces the built-in deny-list
+                    if !skip_types_overridden {
+                        config.skip_types.clear();
+                        skip_types_overridden = true;
+                    }
+                    config
+                        .skip_types
+                        .extend(skip_types.iter().map(|t| t.to_string()));
+                }
+                Attribute::Skip(skip) => config.skip.extend(skip),
+                Attribute::Redact(redact) => config.redact.extend(redact),
+                Attribute::AllowSensitive(allowed) => {
+                    if allowed.is_empty() {
+                        config.allow_all_sensitive = true;
+                    }
+                    config.allow_sensitive.extend(allowed);
+                }
+                Attribute::SensitivePatterns(patterns) => {
+                    if !sensitive_patterns_overridden {
+                        config.sensitive_patterns.clear();
+                        sensitive_patterns_overridden = true;
+                    }
+                    config
+                        .sensitive_patterns
+                        .extend(patterns.iter().map(|p| p.value().to_lowercase()));
+                }
+                Attribute::Strict => config.strict = true,
+                Attribute::Hash(hash) => config.hash.extend(hash),
+                Attribute::Macros(macros) => config.macros.extend(macros),
+                Attribute::LogCompat => config.log_compat = true,
+                Attribute::KeyStyle(key_style) => config.key_style = Some(key_style),
+                Attribute::Prefix(prefix) => config.key_prefix = prefix,
+                Attribute::Name(name) => config.function_name = Some(name),
+                Attribute::Qualified => config.qualified = true,
+                Attribute::NameStyle(name_style) => config.name_style = name_style,
+                Attribute::Location { call_site } => {
+                    config.location = true;
+                    config.call_site_location |= call_site;
+                }
+                Attribute::CallId => config.call_id = true,
+                Attribute::CallPath { max_len } => {
+                    config.call_path = Some(max_len.unwrap_or(DEFAULT_CALL_PATH_LEN));
+                }
+                Attribute::Root => config.root = true,
+                Attribute::Isolate => config.isolate = true,
+                Attribute::RequestId(expr) => config.request_id = Some(expr),
             }
         }
         config
     }
+
+    /// Field key emitted and stored in the context for the source key `raw`, after
+    /// `key_style` and `prefix` are applied
+    fn key(&self, raw: &str) -> String {
+        if self.key_style.is_none() && self.key_prefix.is_empty() {
+            return raw.to_string();
+        }
+        let raw = raw.replace(' ', "");
+        match self.key_style {
+            None => format!("{}.{}", self.key_prefix, raw),
+            Some(KeyStyle::Snake) => {
+                let key = key_words(&raw).join("_");
+                if self.key_prefix.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", self.key_prefix, key)
+                }
+            }
+            Some(KeyStyle::Camel) => {
+                let words = key_words(&self.key_prefix)
+                    .into_iter()
+                    .chain(key_words(&raw));
+                let mut key = String::new();
+                for word in words {
+                    if key.is_empty() {
+                        key.push_str(&word);
+                    } else {
+                        let mut chars = word.chars();
+                        if let Some(first) = chars.next() {
+                            key.extend(first.to_uppercase());
+                            key.push_str(chars.as_str());
+                        }
+                    }
+                }
+                key
+            }
+        }
+    }
+}
+
+/// Casing applied to field keys by `key_style`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum KeyStyle {
+    Snake,
+    Camel,
+}
+
+impl KeyStyle {
+    pub(crate) fn parse(style: &str) -> Option<Self> {
+        match style {
+            "snake" => Some(KeyStyle::Snake),
+            "camel" => Some(KeyStyle::Camel),
+            _ => None,
+        }
+    }
+}
+
+// Lowercase words of a key, split at dots, underscores, other punctuation and case changes,
+// so `user.id`, `user_id` and `userId` all give ["user", "id"]
+fn key_words(key: &str) -> Vec<String> {
+    ident_words(key).iter().map(|word| word.to_lowercase()).collect()
 }
 
 fn get_context_fields_quote(item: &FnItem, config: &AttrConfig) -> Vec<proc_macro2::TokenStream> {
@@ -437,28 +1088,23 @@ fn get_context_fields_quote(item: &FnItem, config: &AttrConfig) -> Vec<proc_macr
     let _has_selective_attributes =
         !config.fields.is_empty() || !config.custom.is_empty() || !config.current.is_empty();
 
-    // For span propagation, automatically inherit parent context fields
-    // This ensures automatic context propagation without manual attributes
-    if config.span && config.fields.is_empty() && config.custom.is_empty() && config.current.is_empty() && !config.all_params {
-        // When only span is enabled (default behavior), inherit all parent context fields
-        // This uses the runtime macro to dynamically include inherited fields
-        field_assignments.push(quote! {
-            context = ::log_args_runtime::get_inherited_context_string()
-        });
-    }
+    // Inherited parent context is added by `log_with_context!` as individual fields,
+    // so no combined context string is needed here
 
     if config.all_params {
         // Log all parameters only when 'all' is explicitly specified
-        let all_args = get_all_args(item);
+        let all_args = get_all_args(item, config);
         for ident in all_args {
-            let ident_str = ident.to_string();
+            let key = config.key(&ident.to_string());
             // When span is enabled, use span context lookup for post-move safety
             if config.span {
                 field_assignments.push(quote! { 
-                    #ident = ::log_args_runtime::get_context_value(&#ident_str).unwrap_or_else(|| "<missing>".to_string())
+                    #key = ::log_args_runtime::get_context_value(&#key).unwrap_or_else(|| "<missing>".to_string())
                 });
+            } else if config.redact.contains(&ident) {
+                field_assignments.push(quote! { #key = #REDACTED_VALUE });
             } else {
-                field_assignments.push(quote! { #ident = ?#ident });
+                field_assignments.push(quote! { #key = ?::log_args_runtime::Scanned(&#ident) });
             }
         }
     }
@@ -467,8 +1113,8 @@ fn get_context_fields_quote(item: &FnItem, config: &AttrConfig) -> Vec<proc_macr
         // Log only specified fields
         for field_expr in &config.fields {
             // Convert complex expressions to string field names
-            let field_name = quote! { #field_expr }.to_string();
-            let field_key = field_name.replace(' ', "");
+            let field_name = config.key(&quote! { #field_expr }.to_string());
+            let field_key = config.key(&quote! { #field_expr }.to_string().replace(' ', ""));
             
             // If clone_upfront is enabled and expression contains self.field, handle it specially
             if config.clone_upfront {
@@ -504,44 +1150,70 @@ fn get_context_fields_quote(item: &FnItem, config: &AttrConfig) -> Vec<proc_macr
                         
                         // Parse the modified string back to a token stream
                         let modified_expr: proc_macro2::TokenStream = modified_expr_str.parse().unwrap_or_else(|_| quote!(#field_expr));
-                        field_assignments.push(quote! { #field_name = ?#modified_expr });
+                        field_assignments.push(quote! { #field_name = ?::log_args_runtime::Scanned(&(#modified_expr)) });
                     }
                 } else {
-                    field_assignments.push(quote! { #field_name = ?#field_expr });
+                    field_assignments.push(quote! { #field_name = ?::log_args_runtime::Scanned(&(#field_expr)) });
                 }
             } else {
-                field_assignments.push(quote! { #field_name = ?#field_expr });
+                field_assignments.push(quote! { #field_name = ?::log_args_runtime::Scanned(&(#field_expr)) });
             }
         }
     }
+    // Add hashed fields: the keyed hash is logged instead of the raw value
+    for hash_expr in &config.hash {
+        let field_key = config.key(&quote! { #hash_expr }.to_string().replace(' ', ""));
+        if config.span {
+            field_assignments.push(quote! {
+                #field_key = ::log_args_runtime::get_context_value(&#field_key).unwrap_or_else(|| "<missing>".to_string())
+            });
+        } else {
+            field_assignments.push(quote! {
+                #field_key = ::log_args_runtime::__hash_field!(&format!("{:?}", &#hash_expr))
+            });
+        }
+    }
+
     // Default behavior: Only enable span propagation and function name logging
     // No automatic parameter logging unless explicitly requested
     // If only custom/current are specified (no fields), we don't log any parameters
 
     // Add custom fields (always included)
     for nv in &config.custom {
-        let key = &nv.path;
+        let path = &nv.path;
+        let key = config.key(&quote!(#path).to_string().replace(' ', ""));
         let value = &nv.value;
-        
-        // Add to logging fields
-        field_assignments.push(quote! {
-            #key = #value
-        });
+
+        // Number and bool literals keep their type. Other values are rendered with
+        // `Display`, as in the context, and go through the scanner.
+        let literal = matches!(
+            value,
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(_) | syn::Lit::Float(_) | syn::Lit::Bool(_),
+                ..
+            })
+        );
+        if literal {
+            field_assignments.push(quote! { #key = #value });
+        } else {
+            field_assignments.push(quote! { #key = %::log_args_runtime::Scanned(&(#value)) });
+        }
     }
 
     // Add current fields (only logged in current function, not propagated)
-    for current_field in &config.current {
-        let field_name = quote! { #current_field }.to_string();
-        let field_key = field_name.replace(' ', "");
+    for (index, current_field) in config.current.iter().enumerate() {
+        let field_name = config.key(&quote! { #current_field }.to_string());
         
         // If clone_upfront is enabled and expression contains self.field, handle it specially
         if config.clone_upfront {
             let expr_str = quote!(#current_field).to_string();
             if expr_str.contains("self.") {
-                // When span is enabled, use span context lookup for post-move safety
+                // When span is enabled, use the value rendered upfront for post-move safety.
+                // It is kept out of the context stack so children don't inherit it.
                 if config.span {
+                    let local = current_field_local(index);
                     field_assignments.push(quote! { 
-                        #field_name = ::log_args_runtime::get_context_value(&#field_key).unwrap_or_else(|| "<missing>".to_string())
+                        #field_name = %#local
                     });
                 } else {
                     // No span, use cloned variable approach (similar to custom fields)
@@ -568,129 +1240,423 @@ fn get_context_fields_quote(item: &FnItem, config: &AttrConfig) -> Vec<proc_macr
                     
                     // Parse the modified string back to a token stream
                     let modified_expr: proc_macro2::TokenStream = modified_expr_str.parse().unwrap_or_else(|_| quote!(#current_field));
-                    field_assignments.push(quote! { #field_name = ?#modified_expr });
+                    field_assignments.push(quote! { #field_name = ?::log_args_runtime::Scanned(&(#modified_expr)) });
                 }
             } else {
-                field_assignments.push(quote! { #field_name = ?#current_field });
+                field_assignments.push(quote! { #field_name = ?::log_args_runtime::Scanned(&(#current_field)) });
             }
         } else {
-            field_assignments.push(quote! { #field_name = ?#current_field });
+            field_assignments.push(quote! { #field_name = ?::log_args_runtime::Scanned(&(#current_field)) });
         }
     }
 
-    // Add function name if any function-names feature is enabled
-    #[cfg(any(
-        feature = "function-names-snake",
-        feature = "function-names-camel",
-        feature = "function-names-pascal",
-        feature = "function-names-screaming",
-        feature = "function-names-kebab"
-    ))]
-    {
-        let function_name = get_function_name(item);
+    // Add function name if a function-names feature or the policy selects a casing style,
+    // or if `name` or `qualified` asks for it
+    if let Some(function_name) = get_function_name_value(item, config) {
         field_assignments.push(quote! { "function" = #function_name });
     }
 
+    // Where the function is defined; call sites are added per macro by `call_site_fields`.
+    // Logged as strings, the same as the propagated entries children see
+    if config.location {
+        let local = location_local();
+        field_assignments.push(quote! { "location.module_path" = #local.0 });
+        field_assignments.push(quote! { "location.file" = #local.1 });
+        field_assignments.push(quote! { "location.line" = %#local.2 });
+    }
+
+    if get_request_id_init(item, config).is_some() {
+        let local = request_id_local();
+        field_assignments.push(quote! { "request_id" = %#local });
+    }
+
+    // Every invocation is told apart by its own id; the parent's id is in the pushed frame
+    if config.call_id {
+        let call_id = call_id_local();
+        field_assignments.push(quote! { "call_id" = %#call_id });
+    }
+
+    if config.call_path.is_some() {
+        let local = call_path_local();
+        field_assignments.push(quote! { "call_path" = %#local.0 });
+        field_assignments.push(quote! { "depth" = %#local.1 });
+    }
+
     field_assignments
 }
 
-#[cfg(any(
-    feature = "function-names-snake",
-    feature = "function-names-camel",
-    feature = "function-names-pascal",
-    feature = "function-names-screaming",
-    feature = "function-names-kebab"
-))]
-fn get_function_name(item: &FnItem) -> String {
-    let function_name = match item {
-        FnItem::Item(item_fn) => item_fn.sig.ident.to_string(),
-        FnItem::ImplItem(impl_item_fn) => impl_item_fn.sig.ident.to_string(),
+/// Casing applied to the `function` field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NameStyle {
+    Snake,
+    Camel,
+    Pascal,
+    Screaming,
+    Kebab,
+}
+
+impl NameStyle {
+    pub(crate) fn parse(style: &str) -> Option<Self> {
+        match style {
+            "snake" => Some(NameStyle::Snake),
+            "camel" => Some(NameStyle::Camel),
+            "pascal" => Some(NameStyle::Pascal),
+            "screaming" => Some(NameStyle::Screaming),
+            "kebab" => Some(NameStyle::Kebab),
+            _ => None,
+        }
+    }
+
+    // Style selected by Cargo features: `function-names` logs PascalCase names, unless
+    // exactly one of the deprecated `function-names-<style>` features picks another style.
+    // Features are additive across a workspace, so several of them fall back to PascalCase
+    // instead of picking an arbitrary winner.
+    fn from_features() -> Option<Self> {
+        if !cfg!(feature = "function-names") {
+            return None;
+        }
+        let legacy = [
+            (cfg!(feature = "function-names-snake"), NameStyle::Snake),
+            (cfg!(feature = "function-names-camel"), NameStyle::Camel),
+            (cfg!(feature = "function-names-pascal"), NameStyle::Pascal),
+            (cfg!(feature = "function-names-screaming"), NameStyle::Screaming),
+            (cfg!(feature = "function-names-kebab"), NameStyle::Kebab),
+        ];
+        let mut enabled = legacy.iter().filter(|(enabled, _)| *enabled);
+        match (enabled.next(), enabled.next()) {
+            (Some((_, style)), None) => Some(*style),
+            _ => Some(NameStyle::Pascal),
+        }
+    }
+}
+
+// Value of the `function` field: the `name` override, or the cased function name, qualified
+// with the impl's self type and trait when `qualified` is set
+fn get_function_name_value(item: &FnItem, config: &AttrConfig) -> Option<proc_macro2::TokenStream> {
+    if let Some(name) = &config.function_name {
+        return Some(quote! { #name });
+    }
+    if config.name_style.is_none() && !config.qualified && config.call_path.is_none() {
+        return None;
+    }
+
+    let function_name = match config.name_style {
+        Some(style) => get_function_name(item, style),
+        None => item.sig().ident.to_string(),
     };
+    if !config.qualified {
+        return Some(quote! { #function_name });
+    }
+    match &config.impl_context {
+        Some(impl_context) => {
+            let qualified = match &impl_context.trait_name {
+                Some(trait_name) => {
+                    format!("<{} as {}>::{}", impl_context.self_ty, trait_name, function_name)
+                }
+                None => format!("{}::{}", impl_context.self_ty, function_name),
+            };
+            Some(quote! { #qualified })
+        }
+        // Without the impl block, the self type of a method is only known at run time.
+        // Associated functions without `self` can't be told apart from free functions.
+        None if item.sig().receiver().is_some() => {
+            let local = function_name_local();
+            Some(quote! { #local })
+        }
+        None => Some(quote! { #function_name }),
+    }
+}
 
-    // Apply the appropriate casing based on enabled features
-    #[cfg(feature = "function-names-snake")]
-    return function_name; // Keep original snake_case
+// Resolve the self type of a `qualified` method decorated on its own, before the body runs
+fn get_function_name_local(item: &FnItem, config: &AttrConfig) -> proc_macro2::TokenStream {
+    let qualified_at_runtime = config.qualified
+        && config.function_name.is_none()
+        && config.impl_context.is_none()
+        && item.sig().receiver().is_some();
+    if !qualified_at_runtime {
+        return quote! {};
+    }
+    let local = function_name_local();
+    let function_name = match config.name_style {
+        Some(style) => get_function_name(item, style),
+        None => item.sig().ident.to_string(),
+    };
+    quote! {
+        let #local = ::log_args_runtime::qualified_function_name(
+            ::std::any::type_name::<Self>(),
+            #function_name,
+        );
+    }
+}
 
-    #[cfg(feature = "function-names-camel")]
-    return to_camel_case(&function_name);
+// Read the definition site once, outside any macro: inside a log macro's expansion `line!()`
+// would report the line of the log call instead
+fn get_location_local(item: &FnItem, config: &AttrConfig) -> proc_macro2::TokenStream {
+    if !config.location {
+        return quote! {};
+    }
+    let local = location_local();
+    let location = quote::quote_spanned! { item.sig().ident.span()=>
+        (module_path!(), file!(), line!())
+    };
+    quote! {
+        let #local: (&'static str, &'static str, u32) = #location;
+    }
+}
 
-    #[cfg(feature = "function-names-pascal")]
-    return to_pascal_case(&function_name);
+/// `location(call_site)` fields for a log macro. `file!()` and `line!()` report the location
+/// of their own tokens, so `span` must point at the macro invocation.
+pub(crate) fn call_site_fields(
+    config: &AttrConfig,
+    span: proc_macro2::Span,
+) -> Vec<proc_macro2::TokenStream> {
+    if !config.call_site_location {
+        return Vec::new();
+    }
+    vec![
+        quote::quote_spanned! { span=> "call_file" = file!() },
+        quote::quote_spanned! { span=> "call_line" = line!() },
+    ]
+}
 
-    #[cfg(feature = "function-names-screaming")]
-    return to_screaming_snake_case(&function_name);
+// Start the scope before anything reads the inherited context, such as the parent call id.
+// The guard is declared before the context guard, so it ends after this function's frame
+// is popped. A `request_id = expr` is evaluated first, so it can still read the caller's
+// context.
+fn get_scope_locals(item: &FnItem, config: &AttrConfig) -> proc_macro2::TokenStream {
+    let request_id = get_request_id_init(item, config).map(|init| {
+        let local = request_id_local();
+        quote! { let #local: String = #init; }
+    });
+    let guard = if config.root {
+        Some(quote! { let __log_args_scope = ::log_args_runtime::enter_root_scope(); })
+    } else if config.isolate {
+        Some(quote! { let __log_args_scope = ::log_args_runtime::enter_isolated_scope(); })
+    } else {
+        None
+    };
+    quote! {
+        #request_id
+        #guard
+    }
+}
 
-    #[cfg(feature = "function-names-kebab")]
-    return to_kebab_case(&function_name);
+// The id from `request_id = expr`, or a generated one for a root that doesn't log a
+// `request_id` itself
+fn get_request_id_init(item: &FnItem, config: &AttrConfig) -> Option<proc_macro2::TokenStream> {
+    if let Some(expr) = &config.request_id {
+        return Some(quote! { ::log_args_runtime::__request_id!(#expr) });
+    }
+    if !config.root || logs_request_id_key(item, config) {
+        return None;
+    }
+    Some(quote! { ::log_args_runtime::__request_id!() })
+}
 
-    // Default fallback when no function name features are enabled
-    #[cfg(not(any(
-        feature = "function-names-snake",
-        feature = "function-names-camel",
-        feature = "function-names-pascal",
-        feature = "function-names-screaming",
-        feature = "function-names-kebab"
-    )))]
-    to_pascal_case(&function_name)
+fn logs_request_id_key(item: &FnItem, config: &AttrConfig) -> bool {
+    let mut keys: Vec<String> = Vec::new();
+    if config.all_params {
+        keys.extend(get_all_args(item, config).iter().map(|ident| ident.to_string()));
+    }
+    keys.extend(config.fields.iter().map(|expr| quote!(#expr).to_string()));
+    keys.extend(config.hash.iter().map(|expr| quote!(#expr).to_string()));
+    keys.extend(config.custom.iter().map(|nv| {
+        let path = &nv.path;
+        quote!(#path).to_string()
+    }));
+    keys.iter()
+        .any(|key| config.key(&key.replace(' ', "")) == "request_id")
 }
 
-// Convert snake_case to camelCase (first letter lowercase)
-#[cfg(feature = "function-names-camel")]
-fn to_camel_case(snake_case: &str) -> String {
-    let words: Vec<&str> = snake_case.split('_').collect();
-    if words.is_empty() {
-        return String::new();
+// `request_id = expr` and a logged `request_id` key would write the same field twice
+fn check_request_id(item: &FnItem, config: &AttrConfig) -> syn::Result<()> {
+    match &config.request_id {
+        Some(expr) if logs_request_id_key(item, config) => Err(syn::Error::new_spanned(
+            expr,
+            "`request_id = ...` conflicts with a logged `request_id` field",
+        )),
+        _ => Ok(()),
     }
+}
 
-    let mut result = words[0].to_lowercase();
-    for word in &words[1..] {
-        if !word.is_empty() {
-            let mut chars = word.chars();
-            if let Some(first) = chars.next() {
-                result.push_str(&first.to_uppercase().collect::<String>());
-                result.push_str(&chars.as_str().to_lowercase());
+// The scope guards swap the thread's context stacks, and an async fn may be suspended on one
+// thread and resumed on another, or interleaved with other tasks on the same one
+fn check_scope(item: &FnItem, config: &AttrConfig) -> syn::Result<()> {
+    let attribute = if config.root {
+        "root"
+    } else if config.isolate {
+        "isolate"
+    } else {
+        return Ok(());
+    };
+    match &item.sig().asyncness {
+        Some(asyncness) => Err(syn::Error::new_spanned(
+            asyncness,
+            format!(
+                "`{}` can't be used on an async fn, since its scope would outlive an `.await`",
+                attribute
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn request_id_local() -> Ident {
+    quote::format_ident!("__log_args_request_id")
+}
+
+// The id is drawn before the frame is pushed, so the enclosing frame's `call_id` is still the
+// innermost one and becomes `parent_call_id`
+fn get_call_id_locals(config: &AttrConfig) -> proc_macro2::TokenStream {
+    if !config.call_id {
+        return quote! {};
+    }
+    let call_id = call_id_local();
+    let parent_call_id = parent_call_id_local();
+    let parent = config.span.then(|| {
+        quote! {
+            let #parent_call_id = ::log_args_runtime::get_context_value("call_id");
+        }
+    });
+    quote! {
+        let #call_id = ::log_args_runtime::next_call_id();
+        #parent
+    }
+}
+
+// Like the parent call id, the path is read from the stack before this function's frame
+// is pushed
+fn get_call_path_local(item: &FnItem, config: &AttrConfig) -> proc_macro2::TokenStream {
+    let (Some(max_len), Some(function_name)) =
+        (config.call_path, get_function_name_value(item, config))
+    else {
+        return quote! {};
+    };
+    let local = call_path_local();
+    quote! {
+        let #local: (String, usize) = ::log_args_runtime::call_path(&#function_name, #max_len);
+    }
+}
+
+fn call_path_local() -> Ident {
+    quote::format_ident!("__log_args_call_path")
+}
+
+fn call_id_local() -> Ident {
+    quote::format_ident!("__log_args_call_id")
+}
+
+fn parent_call_id_local() -> Ident {
+    quote::format_ident!("__log_args_parent_call_id")
+}
+
+fn location_local() -> Ident {
+    quote::format_ident!("__log_args_location")
+}
+
+fn function_name_local() -> Ident {
+    quote::format_ident!("__log_args_function")
+}
+
+fn get_function_name(item: &FnItem, style: NameStyle) -> String {
+    let function_name = match item {
+        FnItem::Item(item_fn) => item_fn.sig.ident.to_string(),
+        FnItem::ImplItem(impl_item_fn) => impl_item_fn.sig.ident.to_string(),
+    };
+
+    match style {
+        NameStyle::Snake => to_snake_case(&function_name),
+        NameStyle::Camel => to_camel_case(&function_name),
+        NameStyle::Pascal => to_pascal_case(&function_name),
+        NameStyle::Screaming => to_screaming_snake_case(&function_name),
+        NameStyle::Kebab => to_kebab_case(&function_name),
+    }
+}
+
+// Words of an identifier, split at underscores and case changes. Acronyms stay together and
+// digits stay with the word before them: `parseHTTPRequest` gives ["parse", "HTTP", "Request"]
+// and `get_v2_user` gives ["get", "v2", "user"].
+fn ident_words(ident: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    for chunk in ident.split(|ch: char| !ch.is_alphanumeric()) {
+        let chars: Vec<(usize, char)> = chunk.char_indices().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (index, ch) = chars[i];
+            let prev = chars[i - 1].1;
+            let next_is_lower = chars.get(i + 1).map_or(false, |(_, next)| next.is_lowercase());
+            let boundary = ch.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_ascii_digit()
+                    || (prev.is_uppercase() && next_is_lower));
+            if boundary {
+                words.push(&chunk[start..index]);
+                start = index;
             }
         }
+        if start < chunk.len() {
+            words.push(&chunk[start..]);
+        }
+    }
+    words
+}
+
+// Leading underscores and the words of an identifier. `r#type` is cased as `type`, and
+// `_internal` keeps its underscore in every style.
+fn split_identifier(ident: &str) -> (&str, Vec<&str>) {
+    let ident = ident.strip_prefix("r#").unwrap_or(ident);
+    let body = ident.trim_start_matches('_');
+    (&ident[..ident.len() - body.len()], ident_words(body))
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.as_str().to_lowercase().chars()).collect(),
+        None => String::new(),
+    }
+}
+
+// Convert an identifier to snake_case
+fn to_snake_case(ident: &str) -> String {
+    let (prefix, words) = split_identifier(ident);
+    let words: Vec<String> = words.iter().map(|word| word.to_lowercase()).collect();
+    format!("{}{}", prefix, words.join("_"))
+}
+
+// Convert an identifier to camelCase (first letter lowercase)
+fn to_camel_case(ident: &str) -> String {
+    let (prefix, words) = split_identifier(ident);
+    let mut result = prefix.to_string();
+    for (index, word) in words.iter().enumerate() {
+        if index == 0 {
+            result.push_str(&word.to_lowercase());
+        } else {
+            result.push_str(&capitalize(word));
+        }
     }
     result
 }
 
-// Convert snake_case to PascalCase (first letter uppercase)
-#[allow(dead_code)]
-#[cfg(any(
-    feature = "function-names-pascal",
-    not(any(
-        feature = "function-names-snake",
-        feature = "function-names-camel",
-        feature = "function-names-screaming",
-        feature = "function-names-kebab"
-    ))
-))]
-fn to_pascal_case(snake_case: &str) -> String {
-    snake_case
-        .split('_')
-        .map(|word| {
-            let mut chars = word.chars();
-            match chars.next() {
-                None => String::new(),
-                Some(first) => {
-                    first.to_uppercase().collect::<String>() + &chars.as_str().to_lowercase()
-                }
-            }
-        })
-        .collect()
+// Convert an identifier to PascalCase (first letter uppercase)
+fn to_pascal_case(ident: &str) -> String {
+    let (prefix, words) = split_identifier(ident);
+    let words: String = words.iter().map(|word| capitalize(word)).collect();
+    format!("{}{}", prefix, words)
 }
 
-// Convert snake_case to SCREAMING_SNAKE_CASE
-#[cfg(feature = "function-names-screaming")]
-fn to_screaming_snake_case(snake_case: &str) -> String {
-    snake_case.to_uppercase()
+// Convert an identifier to SCREAMING_SNAKE_CASE
+fn to_screaming_snake_case(ident: &str) -> String {
+    let (prefix, words) = split_identifier(ident);
+    let words: Vec<String> = words.iter().map(|word| word.to_uppercase()).collect();
+    format!("{}{}", prefix, words.join("_"))
 }
 
-// Convert snake_case to kebab-case
-#[cfg(feature = "function-names-kebab")]
-fn to_kebab_case(snake_case: &str) -> String {
-    snake_case.replace('_', "-")
+// Convert an identifier to kebab-case
+fn to_kebab_case(ident: &str) -> String {
+    let (prefix, words) = split_identifier(ident);
+    let words: Vec<String> = words.iter().map(|word| word.to_lowercase()).collect();
+    format!("{}{}", prefix, words.join("-"))
 }
 
 fn get_context_map_for_span(_item: &FnItem, config: &AttrConfig) -> proc_macro2::TokenStream {
@@ -701,30 +1667,44 @@ fn get_context_map_for_span(_item: &FnItem, config: &AttrConfig) -> proc_macro2:
     
     // 1. Add all parameters if requested
     if config.all_params {
-        let all_args = get_all_args(_item);
+        let all_args = get_all_args(_item, config);
         for ident in all_args {
-            let ident_str = ident.to_string();
-            fields_to_log.push(quote! {
-                new_context.insert(#ident_str.to_string(), format!("{:?}", #ident));
-            });
+            let ident_str = config.key(&ident.to_string());
+            if config.redact.contains(&ident) {
+                fields_to_log.push(quote! {
+                    new_context.insert(#ident_str.to_string(), #REDACTED_VALUE.to_string());
+                });
+            } else {
+                fields_to_log.push(quote! {
+                    new_context.insert(#ident_str.to_string(), format!("{:?}", #ident));
+                });
+            }
         }
     }
     
     // 2. Add explicitly specified fields
     if !config.fields.is_empty() {
         for field_expr in &config.fields {
-            let key_str = quote!(#field_expr).to_string().replace(' ', "");
+            let key_str = config.key(&quote!(#field_expr).to_string().replace(' ', ""));
             fields_to_log.push(quote! {
                 new_context.insert(#key_str.to_string(), format!("{:?}", &#field_expr));
             });
         }
     }
     
-    // 3. Add custom fields (always included)
+    // 3. Add hashed fields, never storing the raw value in the context
+    for hash_expr in &config.hash {
+        let key_str = config.key(&quote!(#hash_expr).to_string().replace(' ', ""));
+        fields_to_log.push(quote! {
+            new_context.insert(#key_str.to_string(), ::log_args_runtime::__hash_field!(&format!("{:?}", &#hash_expr)));
+        });
+    }
+
+    // 4. Add custom fields (always included)
     for nv in &config.custom {
         let key = &nv.path;
         let value = &nv.value;
-        let key_str = quote!(#key).to_string().replace(' ', "");
+        let key_str = config.key(&quote!(#key).to_string().replace(' ', ""));
         
         // For span context, use the original expression directly
         // This will be evaluated before any moves happen
@@ -738,27 +1718,50 @@ fn get_context_map_for_span(_item: &FnItem, config: &AttrConfig) -> proc_macro2:
         });
     }
     
-    // 4. Add current fields (these are also stored in context for consistency)
-    for current_field in &config.current {
-        let field_name = quote! { #current_field }.to_string();
-        let field_key = field_name.replace(' ', "");
+    // Current fields are deliberately not stored: the context is what children inherit
+
+    // Add function name to context if it is logged (always propagated)
+    if let Some(function_name) = get_function_name_value(_item, config) {
         fields_to_log.push(quote! {
-            new_context.insert(#field_key.to_string(), format!("{:?}", #current_field));
+            new_context.insert("function".to_string(), #function_name.to_string());
         });
     }
 
-    // Add function name to context if any function-names feature is enabled (always propagated)
-    #[cfg(any(
-        feature = "function-names-snake",
-        feature = "function-names-camel",
-        feature = "function-names-pascal",
-        feature = "function-names-screaming",
-        feature = "function-names-kebab"
-    ))]
-    {
-        let function_name = get_function_name(_item);
+    // The definition site goes with the function name, so children can be traced back to it.
+    // The keys are namespaced so they aren't mistaken for the child's own location
+    if config.location {
+        let local = location_local();
         fields_to_log.push(quote! {
-            new_context.insert("function".to_string(), #function_name.to_string());
+            new_context.insert("location.module_path".to_string(), #local.0.to_string());
+            new_context.insert("location.file".to_string(), #local.1.to_string());
+            new_context.insert("location.line".to_string(), #local.2.to_string());
+        });
+    }
+
+    if get_request_id_init(_item, config).is_some() {
+        let local = request_id_local();
+        fields_to_log.push(quote! {
+            new_context.insert("request_id".to_string(), #local.clone());
+        });
+    }
+
+    // Children see this invocation as their parent
+    if config.call_id {
+        let call_id = call_id_local();
+        let parent_call_id = parent_call_id_local();
+        fields_to_log.push(quote! {
+            new_context.insert("call_id".to_string(), #call_id.to_string());
+            if let Some(parent_call_id) = #parent_call_id {
+                new_context.insert("parent_call_id".to_string(), parent_call_id);
+            }
+        });
+    }
+
+    if config.call_path.is_some() {
+        let local = call_path_local();
+        fields_to_log.push(quote! {
+            new_context.insert("call_path".to_string(), #local.0.clone());
+            new_context.insert("depth".to_string(), #local.1.to_string());
         });
     }
 
@@ -771,14 +1774,39 @@ fn get_context_map_for_span(_item: &FnItem, config: &AttrConfig) -> proc_macro2:
     }
 }
 
-fn get_all_args(item: &FnItem) -> Vec<Ident> {
+// Render `self.` current fields before the body runs, so they can still be logged after
+// the body moves out of `self` without being pushed to the inherited context
+fn get_current_field_locals(config: &AttrConfig) -> proc_macro2::TokenStream {
+    if !config.span || !config.clone_upfront {
+        return quote! {};
+    }
+    let locals = config.current.iter().enumerate().filter_map(|(index, current_field)| {
+        if !quote!(#current_field).to_string().contains("self.") {
+            return None;
+        }
+        let local = current_field_local(index);
+        Some(quote! {
+            let #local = ::log_args_runtime::scan_value(&format!("{:?}", &(#current_field)));
+        })
+    });
+    quote! { #(#locals)* }
+}
+
+fn current_field_local(index: usize) -> Ident {
+    quote::format_ident!("__log_args_current_{}", index)
+}
+
+fn get_all_args(item: &FnItem, config: &AttrConfig) -> Vec<Ident> {
     item.sig()
         .inputs
         .iter()
         .filter_map(|arg| {
             if let FnArg::Typed(pt) = arg {
                 if let Pat::Ident(pi) = &*pt.pat {
-                    if pi.ident != "self" {
+                    if pi.ident != "self"
+                        && !config.skip.contains(&pi.ident)
+                        && !is_skipped_type(&pt.ty, &config.skip_types)
+                    {
                         return Some(pi.ident.clone());
                     }
                 }
@@ -788,6 +1816,152 @@ fn get_all_args(item: &FnItem) -> Vec<Ident> {
         .collect()
 }
 
+// Reject any logged field key that the project policy denies or does not allow
+fn check_policy(item: &FnItem, config: &AttrConfig, policy: &Policy) -> syn::Result<()> {
+    let mut errors: Option<syn::Error> = None;
+    // Both the source key and the emitted key are checked, since `key_style`, `prefix`
+    // and `[keys]` may rename one into a denied key
+    let mut check = |key: String, tokens: &dyn quote::ToTokens| {
+        if let Err(message) = policy.check_key(&key, &config.key(&key)) {
+            let error = syn::Error::new_spanned(tokens, message);
+            match &mut errors {
+                Some(errors) => errors.combine(error),
+                None => errors = Some(error),
+            }
+        }
+    };
+
+    if config.all_params {
+        for ident in get_all_args(item, config) {
+            check(ident.to_string(), &ident);
+        }
+    }
+    for field_expr in config.fields.iter().chain(&config.current) {
+        check(quote!(#field_expr).to_string().replace(' ', ""), field_expr);
+    }
+    for nv in &config.custom {
+        let key = &nv.path;
+        check(quote!(#key).to_string().replace(' ', ""), key);
+    }
+    for hash_expr in &config.hash {
+        check(quote!(#hash_expr).to_string().replace(' ', ""), hash_expr);
+    }
+
+    match errors {
+        Some(errors) => Err(errors),
+        None => Ok(()),
+    }
+}
+
+// Flag parameters that `all` would log whose name or type matches a sensitive pattern.
+// Each one becomes a deprecation warning, or a spanned error under `strict`.
+fn get_sensitive_warnings(
+    item: &FnItem,
+    config: &AttrConfig,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if !config.all_params || config.allow_all_sensitive {
+        return Ok(quote! {});
+    }
+
+    let logged = get_all_args(item, config);
+    let mut warnings = vec![];
+    let mut errors: Option<syn::Error> = None;
+
+    for arg in &item.sig().inputs {
+        let FnArg::Typed(pt) = arg else { continue };
+        let Pat::Ident(pi) = &*pt.pat else { continue };
+        let ident = &pi.ident;
+        if !logged.contains(ident)
+            || config.redact.contains(ident)
+            || config.allow_sensitive.contains(ident)
+        {
+            continue;
+        }
+
+        let mut names = vec![ident.to_string().trim_start_matches("r#").to_lowercase()];
+        collect_type_names(&pt.ty, &mut names);
+        let Some(pattern) = config
+            .sensitive_patterns
+            .iter()
+            .find(|pattern| names.iter().any(|name| name.contains(pattern.as_str())))
+        else {
+            continue;
+        };
+
+        let message = format!(
+            "#[params(all)] logs sensitive-looking parameter `{}` (matches \"{}\"); add skip({}), redact({}) or allow_sensitive({}) to silence this",
+            ident, pattern, ident, ident, ident
+        );
+        if config.strict {
+            let error = syn::Error::new_spanned(ident, message);
+            match &mut errors {
+                Some(errors) => errors.combine(error),
+                None => errors = Some(error),
+            }
+        } else {
+            let marker = quote::format_ident!("__log_args_sensitive_{}", ident);
+            warnings.push(quote::quote_spanned! { ident.span() =>
+                #[deprecated(note = #message)]
+                #[allow(non_upper_case_globals)]
+                const #marker: () = ();
+                let _ = #marker;
+            });
+        }
+    }
+
+    match errors {
+        Some(errors) => Err(errors),
+        None => Ok(quote! { #(#warnings)* }),
+    }
+}
+
+// Collect the snake_case names of every path segment in a type, e.g. `Arc<ApiKey>` -> `arc`, `api_key`
+fn collect_type_names(ty: &syn::Type, names: &mut Vec<String>) {
+    match ty {
+        syn::Type::Reference(r) => collect_type_names(&r.elem, names),
+        syn::Type::Paren(p) => collect_type_names(&p.elem, names),
+        syn::Type::Group(g) => collect_type_names(&g.elem, names),
+        syn::Type::Path(tp) => {
+            for segment in &tp.path.segments {
+                names.push(to_snake_case(&segment.ident.to_string()));
+                if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+                    for arg in &args.args {
+                        if let syn::GenericArgument::Type(inner) = arg {
+                            collect_type_names(inner, names);
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// Match a parameter type against `skip_types` by the last path segment, looking
+// through references and generic wrappers so `&PgPool` and `Arc<AppState>` match too
+fn is_skipped_type(ty: &syn::Type, skip_types: &[String]) -> bool {
+    match ty {
+        syn::Type::Reference(r) => is_skipped_type(&r.elem, skip_types),
+        syn::Type::Paren(p) => is_skipped_type(&p.elem, skip_types),
+        syn::Type::Group(g) => is_skipped_type(&g.elem, skip_types),
+        syn::Type::Path(tp) => {
+            let Some(segment) = tp.path.segments.last() else {
+                return false;
+            };
+            if skip_types.iter().any(|t| segment.ident == t) {
+                return true;
+            }
+            match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) => args.args.iter().any(|arg| {
+                    matches!(arg, syn::GenericArgument::Type(inner) if is_skipped_type(inner, skip_types))
+                }),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
 enum FnItem {
     Item(syn::ItemFn),
     ImplItem(syn::ImplItemFn),
@@ -841,27 +2015,27 @@ fn get_log_redefines_with_fields(
     quote! {
         macro_rules! info {
             ($($t:tt)*) => {
-                ::log_args_runtime::log_with_context!(::tracing::info, ::log_args_runtime::get_context(), #(#context_fields,)* $($t)*);
+                ::log_args_runtime::log_with_context!(::tracing::info, ::log_args_runtime::get_inherited_context(), #(#context_fields,)* $($t)*)
             };
         }
         macro_rules! warn {
             ($($t:tt)*) => {
-                ::log_args_runtime::log_with_context!(::tracing::warn, ::log_args_runtime::get_context(), #(#context_fields,)* $($t)*);
+                ::log_args_runtime::log_with_context!(::tracing::warn, ::log_args_runtime::get_inherited_context(), #(#context_fields,)* $($t)*)
             };
         }
         macro_rules! error {
             ($($t:tt)*) => {
-                ::log_args_runtime::log_with_context!(::tracing::error, ::log_args_runtime::get_context(), #(#context_fields,)* $($t)*);
+                ::log_args_runtime::log_with_context!(::tracing::error, ::log_args_runtime::get_inherited_context(), #(#context_fields,)* $($t)*)
             };
         }
         macro_rules! debug {
             ($($t:tt)*) => {
-                ::log_args_runtime::log_with_context!(::tracing::debug, ::log_args_runtime::get_context(), #(#context_fields,)* $($t)*);
+                ::log_args_runtime::log_with_context!(::tracing::debug, ::log_args_runtime::get_inherited_context(), #(#context_fields,)* $($t)*)
             };
         }
         macro_rules! trace {
             ($($t:tt)*) => {
-                ::log_args_runtime::log_with_context!(::tracing::trace, ::log_args_runtime::get_context(), #(#context_fields,)* $($t)*);
+                ::log_args_runtime::log_with_context!(::tracing::trace, ::log_args_runtime::get_inherited_context(), #(#context_fields,)* $($t)*)
             };
         }
     }
@@ -0,0 +1,340 @@
+//! Project-wide logging policy read from `log_args.toml` at the crate root.
+//!
+//! ```toml
+//! [fields]
+//! allow = ["user.id", "request_id", "service"]  # only these keys (and their sub-fields) may be logged
+//! deny = ["user.email", "password"]              # these keys (and their sub-fields) may never be logged
+//!
+//! [redaction]
+//! patterns = ["password", "secret", "token"]     # replaces the sensitive parameter patterns
+//! skip_types = ["PgPool", "Secret"]              # replaces the default `skip_types` list
+//!
+//! [function_names]
+//! style = "kebab"                                # snake, camel, pascal, screaming or kebab
+//!
+//! [keys]
+//! style = "camel"                                # snake or camel, applied to every field key
+//! prefix = "args"                                # prepended to every field key
+//!
+//! [defaults]
+//! attributes = ["custom(service = \"billing\")"] # applied before each function's own attributes
+//! ```
+
+use std::path::PathBuf;
+
+use crate::{KeyStyle, NameStyle};
+
+/// File name looked up in `CARGO_MANIFEST_DIR`
+const POLICY_FILE: &str = "log_args.toml";
+
+#[derive(Default)]
+pub(crate) struct Policy {
+    /// Location of the loaded file, used to make Cargo rebuild when it changes
+    pub path: Option<PathBuf>,
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+    pub sensitive_patterns: Option<Vec<String>>,
+    pub skip_types: Option<Vec<String>>,
+    pub name_style: Option<NameStyle>,
+    pub key_style: Option<KeyStyle>,
+    pub key_prefix: Option<String>,
+    pub default_attributes: Vec<String>,
+}
+
+impl Policy {
+    /// Load the policy of the crate being compiled, or an empty policy if it has none
+    pub fn load() -> Result<Self, String> {
+        let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") else {
+            return Ok(Policy::default());
+        };
+        let path = PathBuf::from(manifest_dir).join(POLICY_FILE);
+        if !path.is_file() {
+            return Ok(Policy::default());
+        }
+
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let mut policy = Self::parse(&source)
+            .map_err(|e| format!("invalid {}: {}", path.display(), e))?;
+        policy.path = Some(path);
+        Ok(policy)
+    }
+
+    fn parse(source: &str) -> Result<Self, String> {
+        let table: toml::Table = source.parse().map_err(|e| format!("{}", e))?;
+        let mut policy = Policy::default();
+
+        if let Some(fields) = table.get("fields") {
+            policy.allow = string_list(fields, "fields.allow", "allow")?.unwrap_or_default();
+            policy.deny = string_list(fields, "fields.deny", "deny")?.unwrap_or_default();
+        }
+        if let Some(redaction) = table.get("redaction") {
+            policy.sensitive_patterns = string_list(redaction, "redaction.patterns", "patterns")?
+                .map(|patterns| patterns.iter().map(|p| p.to_lowercase()).collect());
+            policy.skip_types = string_list(redaction, "redaction.skip_types", "skip_types")?;
+        }
+        if let Some(style) = table
+            .get("function_names")
+            .and_then(|function_names| function_names.get("style"))
+        {
+            let style = style
+                .as_str()
+                .ok_or("`function_names.style` must be a string")?;
+            policy.name_style = Some(NameStyle::parse(style).ok_or_else(|| {
+                format!(
+                    "unknown `function_names.style` \"{}\" (expected snake, camel, pascal, screaming or kebab)",
+                    style
+                )
+            })?);
+        }
+        if let Some(keys) = table.get("keys") {
+            if let Some(style) = keys.get("style") {
+                let style = style.as_str().ok_or("`keys.style` must be a string")?;
+                policy.key_style = Some(KeyStyle::parse(style).ok_or_else(|| {
+                    format!("unknown `keys.style` \"{}\" (expected snake or camel)", style)
+                })?);
+            }
+            if let Some(prefix) = keys.get("prefix") {
+                let prefix = prefix.as_str().ok_or("`keys.prefix` must be a string")?;
+                policy.key_prefix = Some(prefix.to_string());
+            }
+        }
+        if let Some(defaults) = table.get("defaults") {
+            policy.default_attributes =
+                string_list(defaults, "defaults.attributes", "attributes")?.unwrap_or_default();
+        }
+
+        Ok(policy)
+    }
+
+    /// Check a field against the allow- and denylists under both its source key and the
+    /// `emitted` key that `key_style` and `prefix` turn it into. It is denied if either
+    /// form is denied and allowed if either form is allowed.
+    pub fn check_key(&self, key: &str, emitted: &str) -> Result<(), String> {
+        let forms = [key, emitted];
+        let field = if key == emitted {
+            format!("field `{}`", key)
+        } else {
+            format!("field `{}` (logged as `{}`)", key, emitted)
+        };
+        if let Some(denied) = self
+            .deny
+            .iter()
+            .find(|entry| forms.iter().any(|form| key_matches(form, entry)))
+        {
+            return Err(format!(
+                "{} is denied by {} (deny entry \"{}\")",
+                field, POLICY_FILE, denied
+            ));
+        }
+        if !self.allow.is_empty()
+            && !self
+                .allow
+                .iter()
+                .any(|entry| forms.iter().any(|form| key_matches(form, entry)))
+        {
+            return Err(format!("{} is not in the {} allowlist", field, POLICY_FILE));
+        }
+        Ok(())
+    }
+}
+
+// An entry matches the key itself and any of its sub-fields, so "user" covers "user.id"
+fn key_matches(key: &str, entry: &str) -> bool {
+    key == entry
+        || key
+            .strip_prefix(entry)
+            .map_or(false, |rest| rest.starts_with('.'))
+}
+
+fn string_list(
+    section: &toml::Value,
+    name: &str,
+    key: &str,
+) -> Result<Option<Vec<String>>, String> {
+    let Some(value) = section.get(key) else {
+        return Ok(None);
+    };
+    let invalid = || format!("`{}` must be an array of strings", name);
+    value
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|item| item.as_str().map(str::to_string).ok_or_else(invalid))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allow: &[&str], deny: &[&str]) -> Policy {
+        Policy {
+            allow: allow.iter().map(|key| key.to_string()).collect(),
+            deny: deny.iter().map(|key| key.to_string()).collect(),
+            ..Policy::default()
+        }
+    }
+
+    #[test]
+    fn parses_every_section() {
+        let policy = Policy::parse(
+            r#"
+            [fields]
+            allow = ["user.id", "request_id"]
+            deny = ["user.email"]
+
+            [redaction]
+            patterns = ["Password", "TOKEN"]
+            skip_types = ["PgPool"]
+
+            [function_names]
+            style = "kebab"
+
+            [keys]
+            style = "camel"
+            prefix = "args"
+
+            [defaults]
+            attributes = ["custom(service = \"billing\")"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(policy.allow, ["user.id", "request_id"]);
+        assert_eq!(policy.deny, ["user.email"]);
+        assert_eq!(
+            policy.sensitive_patterns.as_deref(),
+            Some(&["password".to_string(), "token".to_string()][..])
+        );
+        assert_eq!(
+            policy.skip_types.as_deref(),
+            Some(&["PgPool".to_string()][..])
+        );
+        assert_eq!(policy.name_style, Some(NameStyle::Kebab));
+        assert_eq!(policy.key_style, Some(KeyStyle::Camel));
+        assert_eq!(policy.key_prefix.as_deref(), Some("args"));
+        assert_eq!(policy.default_attributes, ["custom(service = \"billing\")"]);
+        assert!(policy.path.is_none());
+    }
+
+    #[test]
+    fn empty_file_is_an_empty_policy() {
+        let policy = Policy::parse("").unwrap();
+        assert!(policy.allow.is_empty() && policy.deny.is_empty());
+        assert!(policy.sensitive_patterns.is_none() && policy.skip_types.is_none());
+        assert!(policy.name_style.is_none() && policy.key_style.is_none());
+        assert!(policy.key_prefix.is_none() && policy.default_attributes.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        for (source, expected) in [
+            ("[fields", "expected"),
+            (
+                "[fields]\nallow = \"user\"",
+                "`fields.allow` must be an array of strings",
+            ),
+            (
+                "[fields]\ndeny = [1]",
+                "`fields.deny` must be an array of strings",
+            ),
+            (
+                "[redaction]\nskip_types = [true]",
+                "`redaction.skip_types` must be an array",
+            ),
+            (
+                "[function_names]\nstyle = \"title\"",
+                "unknown `function_names.style` \"title\"",
+            ),
+            (
+                "[function_names]\nstyle = 1",
+                "`function_names.style` must be a string",
+            ),
+            (
+                "[keys]\nstyle = \"kebab\"",
+                "unknown `keys.style` \"kebab\"",
+            ),
+            ("[keys]\nprefix = 1", "`keys.prefix` must be a string"),
+            (
+                "[defaults]\nattributes = \"all\"",
+                "`defaults.attributes` must be an array",
+            ),
+        ] {
+            let error = Policy::parse(source).err().unwrap();
+            assert!(error.contains(expected), "{:?}: {}", source, error);
+        }
+    }
+
+    #[test]
+    fn key_matches_the_entry_and_its_sub_fields() {
+        assert!(key_matches("user", "user"));
+        assert!(key_matches("user.id", "user"));
+        assert!(key_matches("user.address.city", "user.address"));
+        assert!(!key_matches("username", "user"));
+        assert!(!key_matches("user", "user.id"));
+        assert!(!key_matches("app.user", "user"));
+    }
+
+    #[test]
+    fn check_key_applies_the_denylist() {
+        let policy = policy(&[], &["user.email", "password"]);
+        assert!(policy.check_key("user.id", "user.id").is_ok());
+        assert!(policy.check_key("password_hint", "password_hint").is_ok());
+        let error = policy.check_key("user.email", "user.email").unwrap_err();
+        assert_eq!(
+            error,
+            "field `user.email` is denied by log_args.toml (deny entry \"user.email\")"
+        );
+        assert!(policy.check_key("password.old", "password.old").is_err());
+    }
+
+    #[test]
+    fn check_key_applies_the_allowlist() {
+        let policy = policy(&["user.id", "request_id"], &[]);
+        assert!(policy.check_key("user.id", "user.id").is_ok());
+        assert!(policy
+            .check_key("request_id.source", "request_id.source")
+            .is_ok());
+        assert_eq!(
+            policy.check_key("user.name", "user.name").unwrap_err(),
+            "field `user.name` is not in the log_args.toml allowlist"
+        );
+        assert!(policy.check_key("user", "user").is_err());
+    }
+
+    #[test]
+    fn deny_wins_over_allow() {
+        let policy = policy(&["user"], &["user.email"]);
+        assert!(policy.check_key("user.id", "user.id").is_ok());
+        assert!(policy
+            .check_key("user.email", "user.email")
+            .unwrap_err()
+            .contains("denied"));
+    }
+
+    #[test]
+    fn check_key_checks_the_emitted_key_too() {
+        let prefixed = policy(&[], &["app.user_id"]);
+        assert!(prefixed.check_key("user_id", "user_id").is_ok());
+        assert_eq!(
+            prefixed.check_key("user_id", "app.user_id").unwrap_err(),
+            "field `user_id` (logged as `app.user_id`) is denied by log_args.toml (deny entry \"app.user_id\")"
+        );
+
+        let camel = policy(&[], &["user.id"]);
+        assert!(camel.check_key("user.id", "userId").is_err());
+
+        // Allowlists may name either form
+        let allowed = policy(&["user_id", "app.request_id"], &[]);
+        assert!(allowed.check_key("user_id", "app.user_id").is_ok());
+        assert!(allowed.check_key("request_id", "app.request_id").is_ok());
+        assert!(allowed.check_key("tenant", "app.tenant").is_err());
+    }
+
+    #[test]
+    fn empty_policy_allows_everything() {
+        assert!(Policy::default().check_key("password", "password").is_ok());
+    }
+}
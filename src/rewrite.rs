@@ -0,0 +1,443 @@
+//! Rewrites fully-qualified and aliased log macros inside a decorated body.
+//!
+//! Bare `info!`, `warn!` etc. are handled by shadowing `macro_rules!` definitions, but
+//! `tracing::info!`, `log::warn!`, `tracing::event!(Level::INFO, ...)` and macros imported
+//! under another name (`use tracing::info as log_info;`) resolve by path and skip them.
+//! Bare `event!` is only rewritten when the body imports it from `tracing`.
+//! These calls are rewritten to `log_with_context!` around `tracing::event!`, with the
+//! configured fields inserted after the level.
+//!
+//! Macros listed in `#[params(macros(...))]` keep being called directly, with the configured
+//! fields as leading arguments, so they may log several events or return a value.
+//!
+//! With `#[params(log_compat)]`, bare level macros and `log::` calls keep `log` syntax and
+//! are emitted through the `log` facade, with the configured fields as key-values.
+
+use std::collections::HashMap;
+
+use proc_macro2::{Delimiter, Group, Punct, Spacing, TokenStream, TokenTree};
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+
+use crate::AttrConfig;
+
+const LEVELS: &[&str] = &["info", "warn", "error", "debug", "trace"];
+
+#[derive(Clone, Copy)]
+enum LogMacro {
+    /// `info!`, `warn!`, ... from `tracing`, or from `log` when `log` is set
+    Level { name: &'static str, log: bool },
+    /// `tracing::event!` with an explicit level
+    Event,
+}
+
+/// Rewrite every recognized log macro in `block`, and every macro listed in
+/// `macros(...)`, so it carries `context_fields`
+pub(crate) fn rewrite_log_macros(
+    block: &mut syn::Block,
+    context_fields: &[TokenStream],
+    config: &AttrConfig,
+) {
+    let mut aliases = AliasCollector::default();
+    aliases.visit_block_mut(block);
+
+    let mut rewriter = Rewriter {
+        aliases: aliases.aliases,
+        custom_macros: &config.macros,
+        log_compat: config.log_compat,
+        context_fields,
+        config,
+    };
+    rewriter.visit_block_mut(block);
+}
+
+fn classify(crate_name: &str, name: &str) -> Option<LogMacro> {
+    let log = match crate_name {
+        "tracing" => false,
+        "log" => true,
+        _ => return None,
+    };
+    if let Some(level) = LEVELS.iter().find(|level| **level == name) {
+        return Some(LogMacro::Level { name: level, log });
+    }
+    (name == "event" && !log).then_some(LogMacro::Event)
+}
+
+// ===== `use` aliases declared in the body =====
+
+#[derive(Default)]
+struct AliasCollector {
+    aliases: HashMap<String, LogMacro>,
+}
+
+impl AliasCollector {
+    fn collect(&mut self, tree: &syn::UseTree, crate_name: Option<&str>) {
+        match tree {
+            syn::UseTree::Path(path) => {
+                // Only the first segment names the crate; `tracing::level_filters::...` is ignored
+                if crate_name.is_none() {
+                    self.collect(&path.tree, Some(&path.ident.to_string()));
+                }
+            }
+            syn::UseTree::Name(name) => {
+                if let Some(kind) = crate_name.and_then(|c| classify(c, &name.ident.to_string())) {
+                    self.aliases.insert(name.ident.to_string(), kind);
+                }
+            }
+            syn::UseTree::Rename(rename) => {
+                if let Some(kind) = crate_name.and_then(|c| classify(c, &rename.ident.to_string()))
+                {
+                    self.aliases.insert(rename.rename.to_string(), kind);
+                }
+            }
+            syn::UseTree::Group(group) => {
+                for tree in &group.items {
+                    self.collect(tree, crate_name);
+                }
+            }
+            syn::UseTree::Glob(_) => {}
+        }
+    }
+}
+
+impl VisitMut for AliasCollector {
+    fn visit_item_use_mut(&mut self, item: &mut syn::ItemUse) {
+        let known = self.aliases.len();
+        self.collect(&item.tree, None);
+        // Rewritten calls no longer go through the import
+        if self.aliases.len() > known {
+            item.attrs.push(syn::parse_quote!(#[allow(unused_imports)]));
+        }
+    }
+}
+
+// ===== rewriting =====
+
+struct Rewriter<'a> {
+    aliases: HashMap<String, LogMacro>,
+    custom_macros: &'a [syn::Path],
+    log_compat: bool,
+    context_fields: &'a [TokenStream],
+    config: &'a AttrConfig,
+}
+
+impl Rewriter<'_> {
+    // The configured fields plus the call site of the macro at `span`, if requested
+    fn fields(&self, span: proc_macro2::Span) -> Vec<TokenStream> {
+        let mut fields = self.context_fields.to_vec();
+        fields.extend(crate::call_site_fields(self.config, span));
+        fields
+    }
+
+    fn resolve(&self, path: &syn::Path) -> Option<LogMacro> {
+        let segments: Vec<String> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+        match segments.as_slice() {
+            [crate_name, name] => classify(crate_name, name),
+            // Bare level macros are already shadowed, unless they take `log` syntax. A bare
+            // `event!` is only rewritten when imported from `tracing` in the body, as it may
+            // be the user's own macro.
+            [name] if path.leading_colon.is_none() => {
+                self.aliases.get(name).copied().or_else(|| {
+                    if self.log_compat {
+                        classify("log", name)
+                    } else {
+                        None
+                    }
+                })
+            }
+            _ => None,
+        }
+    }
+
+    // Everything becomes `event!` with an explicit target: only its `target:` arms accept
+    // the string-literal field names used for configured fields
+    fn rewrite(&self, kind: LogMacro, tokens: TokenStream, span: proc_macro2::Span) -> TokenStream {
+        let context_fields = self.fields(span);
+        let mut args = Args::new(tokens);
+        let named = args.take_named_args();
+        let (level, rest) = match kind {
+            LogMacro::Level { name, log } => {
+                let level = syn::Ident::new(&name.to_uppercase(), proc_macro2::Span::call_site());
+                let rest = if log {
+                    log_kv_to_fields(args.rest())
+                } else {
+                    args.rest()
+                };
+                (quote! { ::tracing::Level::#level, }, rest)
+            }
+            LogMacro::Event => {
+                let mut level = args.take_segment();
+                if !ends_with_comma(&level) {
+                    level.extend([comma()]);
+                }
+                (level, args.rest())
+            }
+        };
+
+        let name = named.name.map(|name| quote! { name: #name, });
+        let target = named.target.unwrap_or_else(|| quote! { module_path!() });
+        let parent = named.parent.map(|parent| quote! { parent: #parent, });
+        quote! {
+            ::tracing::event, ::log_args_runtime::get_inherited_context(),
+            [#name target: #target, #parent #level] #(#context_fields,)* #rest
+        }
+    }
+
+    // `log::info!(target: ..., kvs; fmt)` becomes `__log_compat!(Info, target: ..., fields, kvs; fmt)`,
+    // which calls `log!` when the runtime has its `log` feature
+    fn rewrite_log_facade(
+        &self,
+        name: &str,
+        tokens: TokenStream,
+        span: proc_macro2::Span,
+    ) -> TokenStream {
+        let mut args = Args::new(tokens);
+        let target = args
+            .take_named_args()
+            .target
+            .unwrap_or_else(|| quote! { module_path!() });
+        let level = syn::Ident::new(&capitalize(name), proc_macro2::Span::call_site());
+
+        let rest: Vec<TokenTree> = args.rest().into_iter().collect();
+        let (user_kvs, message) = match rest
+            .iter()
+            .position(|token| matches!(token, TokenTree::Punct(p) if p.as_char() == ';'))
+        {
+            Some(semicolon) => (&rest[..semicolon], &rest[semicolon + 1..]),
+            None => (&rest[..0], &rest[..]),
+        };
+        let message: TokenStream = message.iter().cloned().collect();
+
+        let mut kvs: Vec<TokenStream> = self
+            .fields(span)
+            .into_iter()
+            .map(tracing_field_to_log_kv)
+            .collect();
+        if !user_kvs.is_empty() {
+            kvs.push(user_kvs.iter().cloned().collect());
+        }
+        if kvs.is_empty() {
+            quote! { #level, target: #target, #message }
+        } else {
+            quote! { #level, target: #target, #(#kvs),*; #message }
+        }
+    }
+}
+
+impl VisitMut for Rewriter<'_> {
+    fn visit_macro_mut(&mut self, mac: &mut syn::Macro) {
+        if self
+            .custom_macros
+            .iter()
+            .any(|custom| same_path(custom, &mac.path))
+        {
+            // User macros keep their own syntax; the fields simply come first
+            let tokens = std::mem::take(&mut mac.tokens);
+            let context_fields = self.fields(mac.path.span());
+            mac.tokens = quote! { #(#context_fields,)* #tokens };
+        } else if let Some(kind) = self.resolve(&mac.path) {
+            // The replacement keeps the original span, so `line!()` inside the expansion
+            // reports the log call rather than the attribute
+            let span = mac.path.span();
+            let tokens = std::mem::take(&mut mac.tokens);
+            match kind {
+                LogMacro::Level { name, log: true } if self.log_compat => {
+                    mac.tokens = self.rewrite_log_facade(name, tokens, span);
+                    mac.path =
+                        syn::parse_quote_spanned!(span=> ::log_args_runtime::__log_compat);
+                }
+                _ => {
+                    mac.tokens = self.rewrite(kind, tokens, span);
+                    mac.path =
+                        syn::parse_quote_spanned!(span=> ::log_args_runtime::__log_with_context);
+                }
+            }
+        }
+    }
+
+    // Nested items can't see the function's locals, so their macros are left alone
+    fn visit_item_mut(&mut self, _item: &mut syn::Item) {}
+}
+
+#[derive(Default)]
+struct NamedArgs {
+    name: Option<TokenStream>,
+    target: Option<TokenStream>,
+    parent: Option<TokenStream>,
+}
+
+fn same_path(a: &syn::Path, b: &syn::Path) -> bool {
+    a.leading_colon.is_some() == b.leading_colon.is_some()
+        && a.segments.len() == b.segments.len()
+        && a.segments
+            .iter()
+            .zip(&b.segments)
+            .all(|(a, b)| a.ident == b.ident)
+}
+
+/// Macro arguments split on top-level commas
+struct Args {
+    tokens: proc_macro2::token_stream::IntoIter,
+}
+
+impl Args {
+    fn new(tokens: TokenStream) -> Self {
+        Self {
+            tokens: tokens.into_iter(),
+        }
+    }
+
+    /// Take the next argument including its trailing comma
+    fn take_segment(&mut self) -> TokenStream {
+        let mut segment = TokenStream::new();
+        for token in self.tokens.by_ref() {
+            let is_comma = matches!(&token, TokenTree::Punct(p) if p.as_char() == ',');
+            segment.extend([token]);
+            if is_comma {
+                break;
+            }
+        }
+        segment
+    }
+
+    /// Take leading `name: ...,`, `target: ...,` and `parent: ...,` arguments
+    fn take_named_args(&mut self) -> NamedArgs {
+        let mut named = NamedArgs::default();
+        loop {
+            let mut lookahead = self.tokens.clone();
+            let slot = match (lookahead.next(), lookahead.next()) {
+                (Some(TokenTree::Ident(ident)), Some(TokenTree::Punct(colon)))
+                    if colon.as_char() == ':' && colon.spacing() == Spacing::Alone =>
+                {
+                    match ident.to_string().as_str() {
+                        "name" => &mut named.name,
+                        "target" => &mut named.target,
+                        "parent" => &mut named.parent,
+                        _ => return named,
+                    }
+                }
+                _ => return named,
+            };
+            // Drop the `key:` and the trailing comma, keeping only the value
+            let mut value: Vec<TokenTree> = self.take_segment().into_iter().skip(2).collect();
+            if matches!(value.last(), Some(TokenTree::Punct(p)) if p.as_char() == ',') {
+                value.pop();
+            }
+            *slot = Some(value.into_iter().collect());
+        }
+    }
+
+    fn rest(self) -> TokenStream {
+        self.tokens.collect()
+    }
+}
+
+/// Convert `log`'s `key = value, key:? = value; "message"` syntax to tracing fields
+fn log_kv_to_fields(tokens: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let Some(semicolon) = tokens
+        .iter()
+        .position(|token| matches!(token, TokenTree::Punct(p) if p.as_char() == ';'))
+    else {
+        return tokens.into_iter().collect();
+    };
+
+    let mut fields = TokenStream::new();
+    let mut args = Args::new(tokens[..semicolon].iter().cloned().collect());
+    loop {
+        let segment: Vec<TokenTree> = args.take_segment().into_iter().collect();
+        if segment.is_empty() {
+            break;
+        }
+        fields.extend(log_kv_to_field(segment));
+    }
+    if !fields.is_empty() && !ends_with_comma(&fields) {
+        fields.extend([comma()]);
+    }
+    fields.extend(tokens[semicolon + 1..].iter().cloned());
+    fields
+}
+
+// `key:? = value` becomes `key = ?value` and `key:% = value` becomes `key = %value`
+fn log_kv_to_field(segment: Vec<TokenTree>) -> TokenStream {
+    match segment.as_slice() {
+        [key @ TokenTree::Ident(_), TokenTree::Punct(colon), TokenTree::Punct(sigil), TokenTree::Punct(eq), value @ ..]
+            if colon.as_char() == ':'
+                && matches!(sigil.as_char(), '?' | '%')
+                && eq.as_char() == '=' =>
+        {
+            let mut field: TokenStream = [key.clone(), TokenTree::Punct(eq.clone())]
+                .into_iter()
+                .collect();
+            // Group the value so the sigil applies to the whole expression
+            let (value, comma) = match value.split_last() {
+                Some((TokenTree::Punct(p), value)) if p.as_char() == ',' => {
+                    (value, Some(p.clone()))
+                }
+                _ => (value, None),
+            };
+            field.extend([
+                TokenTree::Punct(Punct::new(sigil.as_char(), Spacing::Alone)),
+                TokenTree::Group(Group::new(
+                    Delimiter::Parenthesis,
+                    value.iter().cloned().collect(),
+                )),
+            ]);
+            field.extend(comma.map(TokenTree::Punct));
+            field
+        }
+        _ => segment.into_iter().collect(),
+    }
+}
+
+fn ends_with_comma(tokens: &TokenStream) -> bool {
+    matches!(tokens.clone().into_iter().last(), Some(TokenTree::Punct(p)) if p.as_char() == ',')
+}
+
+fn comma() -> TokenTree {
+    TokenTree::Punct(Punct::new(',', Spacing::Alone))
+}
+
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+}
+
+// `key = ?value` becomes `key:? = value`; other values are captured with `Display`.
+// Keys that aren't a single token (`user.id`) become string literals.
+fn tracing_field_to_log_kv(field: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = field.into_iter().collect();
+    let Some(eq) = tokens.iter().position(
+        |token| matches!(token, TokenTree::Punct(p) if p.as_char() == '=' && p.spacing() == Spacing::Alone),
+    ) else {
+        return tokens.into_iter().collect();
+    };
+
+    let key: TokenStream = match &tokens[..eq] {
+        [key] => key.clone().into(),
+        key => {
+            let name: String = key.iter().map(|token| token.to_string()).collect();
+            let name = syn::LitStr::new(&name, proc_macro2::Span::call_site());
+            quote! { #name }
+        }
+    };
+    let (capture, value) = match &tokens[eq + 1..] {
+        [TokenTree::Punct(sigil), value @ ..] if matches!(sigil.as_char(), '?' | '%') => {
+            (sigil.as_char(), value)
+        }
+        value => ('%', value),
+    };
+
+    let mut kv = key;
+    kv.extend([
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Punct(Punct::new(capture, Spacing::Alone)),
+        TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+    ]);
+    kv.extend(value.iter().cloned());
+    kv
+}
@@ -0,0 +1,87 @@
+//! Tests for `call_id`, which numbers every invocation and records the enclosing
+//! decorated call as `parent_call_id`
+
+mod common;
+
+use common::capture_logs;
+use log_args::params;
+
+#[params(call_id, fields(user_id))]
+fn load_profile(user_id: u64) {
+    info!("Loading profile");
+    read_cache();
+}
+
+fn read_cache() {
+    log_args_runtime::info!("Reading cache");
+}
+
+#[params(call_id)]
+fn handle_request() {
+    info!("Handling request");
+    load_profile(1);
+    load_profile(2);
+}
+
+#[test]
+fn test_each_invocation_has_its_own_call_id() {
+    let logs = capture_logs(handle_request);
+    assert_eq!(logs.len(), 5);
+
+    let call_ids: Vec<&str> = logs
+        .iter()
+        .map(|log| log["fields"]["call_id"].as_str().unwrap())
+        .collect();
+    // Handler, first load, its cache read, second load, its cache read
+    assert_ne!(call_ids[0], call_ids[1]);
+    assert_ne!(call_ids[1], call_ids[3]);
+    assert_ne!(call_ids[0], call_ids[3]);
+    assert_eq!(call_ids[1], call_ids[2]);
+    assert_eq!(call_ids[3], call_ids[4]);
+}
+
+#[test]
+fn test_parent_call_id_links_to_the_enclosing_call() {
+    let logs = capture_logs(handle_request);
+    let handler_id = logs[0]["fields"]["call_id"].as_str().unwrap();
+
+    assert!(logs[0]["fields"]["parent_call_id"].is_null());
+    for log in &logs[1..] {
+        assert_eq!(log["fields"]["parent_call_id"].as_str(), Some(handler_id));
+    }
+}
+
+#[test]
+fn test_call_ids_are_stored_in_the_context() {
+    let _guard = log_args_runtime::push_context(Default::default());
+    let mut seen = None;
+    #[params(call_id)]
+    fn outer(seen: &mut Option<(String, String)>) {
+        inner(seen);
+    }
+    #[params(call_id)]
+    fn inner(seen: &mut Option<(String, String)>) {
+        *seen = Some((
+            log_args_runtime::get_context_value("call_id").unwrap(),
+            log_args_runtime::get_context_value("parent_call_id").unwrap(),
+        ));
+    }
+    outer(&mut seen);
+
+    let (call_id, parent_call_id) = seen.unwrap();
+    assert_ne!(call_id, parent_call_id);
+    assert!(call_id.parse::<u64>().is_ok());
+    assert!(parent_call_id.parse::<u64>().is_ok());
+    assert!(log_args_runtime::get_context_value("call_id").is_none());
+}
+
+#[params]
+fn unnumbered() {
+    info!("Not numbered");
+}
+
+#[test]
+fn test_call_id_is_opt_in() {
+    let logs = capture_logs(unnumbered);
+    assert!(logs[0]["fields"]["call_id"].is_null());
+}
@@ -0,0 +1,80 @@
+//! Tests for `call_path` and `depth`, which show the chain of decorated functions
+//! that led to a log
+
+mod common;
+
+use common::capture_logs;
+use log_args::params;
+
+#[params(call_path, name_style = "pascal")]
+fn handle_request() {
+    info!("Handling request");
+    validate_user();
+}
+
+#[params(call_path, name_style = "pascal")]
+fn validate_user() {
+    load_profile();
+}
+
+#[params(call_path, name_style = "pascal")]
+fn load_profile() {
+    info!("Loading profile");
+    read_cache();
+}
+
+fn read_cache() {
+    log_args_runtime::info!("Reading cache");
+}
+
+#[test]
+fn test_call_path_and_depth() {
+    let logs = capture_logs(handle_request);
+    assert_eq!(logs.len(), 3);
+
+    assert_eq!(
+        logs[0]["fields"]["call_path"].as_str(),
+        Some("HandleRequest")
+    );
+    assert_eq!(logs[0]["fields"]["depth"].as_str(), Some("1"));
+
+    let path = "HandleRequest>ValidateUser>LoadProfile";
+    assert_eq!(logs[1]["fields"]["call_path"].as_str(), Some(path));
+    assert_eq!(logs[1]["fields"]["depth"].as_str(), Some("3"));
+
+    // Undecorated children inherit the path of the innermost decorated call, with the same types
+    assert_eq!(logs[2]["fields"]["call_path"].as_str(), Some(path));
+    assert_eq!(logs[2]["fields"]["depth"].as_str(), Some("3"));
+}
+
+#[params(call_path(max_len = 2), name_style = "snake")]
+fn recurse(remaining: u32) {
+    if remaining == 0 {
+        info!("Bottom");
+    } else {
+        recurse(remaining - 1);
+    }
+}
+
+#[test]
+fn test_long_paths_keep_the_innermost_functions() {
+    let logs = capture_logs(|| recurse(3));
+    assert_eq!(logs.len(), 1);
+
+    let fields = &logs[0]["fields"];
+    assert_eq!(fields["call_path"].as_str(), Some("...>recurse>recurse"));
+    assert_eq!(fields["depth"].as_str(), Some("4"));
+    assert_eq!(fields["function"].as_str(), Some("recurse"));
+}
+
+#[params]
+fn no_path() {
+    info!("No path");
+}
+
+#[test]
+fn test_call_path_is_opt_in() {
+    let logs = capture_logs(no_path);
+    assert!(logs[0]["fields"]["call_path"].is_null());
+    assert!(logs[0]["fields"]["depth"].is_null());
+}
@@ -0,0 +1,76 @@
+//! Helpers shared by the integration tests
+//!
+//! Every test binary compiles its own copy and only uses some of the helpers.
+#![allow(dead_code)]
+
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tracing::Subscriber;
+use tracing_subscriber::{fmt, prelude::*, Registry};
+
+/// A mock writer that captures logs into a shared buffer for testing
+#[derive(Clone)]
+pub struct MockWriter {
+    buf: Arc<Mutex<Vec<u8>>>,
+}
+
+impl MockWriter {
+    pub fn new() -> Self {
+        Self {
+            buf: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn get_logs(&self) -> String {
+        let mut buf = self.buf.lock().unwrap();
+        let output = String::from_utf8_lossy(&buf).to_string();
+        buf.clear();
+        output
+    }
+}
+
+impl std::io::Write for MockWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buf.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.buf.lock().unwrap().flush()
+    }
+}
+
+/// Run `f` under the subscriber that `subscriber` builds around a fresh writer, and
+/// return everything written to it
+pub fn capture_output<S, F>(subscriber: impl FnOnce(MockWriter) -> S, f: F) -> String
+where
+    S: Subscriber + Send + Sync + 'static,
+    F: FnOnce(),
+{
+    let writer = MockWriter::new();
+    tracing::subscriber::with_default(subscriber(writer.clone()), f);
+    writer.get_logs()
+}
+
+/// Parse output with one JSON document per line
+pub fn parse_json_lines(output: &str) -> Vec<Value> {
+    output
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Failed to parse log as JSON"))
+        .collect()
+}
+
+/// Logs written by `f` through the JSON formatter of `tracing-subscriber`
+pub fn capture_logs<F: FnOnce()>(f: F) -> Vec<Value> {
+    let output = capture_output(
+        |writer| {
+            Registry::default().with(
+                fmt::layer()
+                    .json()
+                    .with_target(true)
+                    .with_writer(move || writer.clone()),
+            )
+        },
+        f,
+    );
+    parse_json_lines(&output)
+}
@@ -0,0 +1,151 @@
+//! Tests for `LogArgsLayer` and `ContextLayer`, which add inherited context to events
+//! logged through plain `tracing` macros
+
+mod common;
+
+use log_args::params;
+use log_args_runtime::{ContextLayer, LogArgsLayer};
+use serde_json::Value;
+use tracing_subscriber::{fmt, prelude::*, Registry};
+
+// Undecorated helper that logs through plain tracing
+fn load_invoices() {
+    tracing::info!(count = 3, "Loading invoices");
+}
+
+#[params(fields(company_id, user_id))]
+fn handle_request(company_id: u64, user_id: String) {
+    load_invoices();
+}
+
+fn retry_for_company() {
+    tracing::warn!(company_id = 0, "Retrying with fallback company");
+}
+
+#[params(fields(company_id))]
+fn handle_retry(company_id: u64) {
+    info!("Handling retry");
+    retry_for_company();
+}
+
+#[test]
+fn test_layer_flattens_inherited_context() {
+    let logs = common::capture_output(
+        |writer| Registry::default().with(LogArgsLayer::new().with_writer(move || writer.clone())),
+        || {
+            handle_request(42, "alice".to_string());
+        },
+    );
+    let log_json: Value = serde_json::from_str(logs.trim()).expect("Failed to parse log as JSON");
+
+    assert_eq!(log_json["level"].as_str(), Some("INFO"));
+    assert_eq!(
+        log_json["fields"]["message"].as_str(),
+        Some("Loading invoices")
+    );
+    assert_eq!(log_json["fields"]["count"].as_i64(), Some(3));
+    assert_eq!(log_json["fields"]["company_id"].as_str(), Some("42"));
+    assert_eq!(log_json["fields"]["user_id"].as_str(), Some("\"alice\""));
+    assert!(log_json["fields"]["context"].is_null());
+}
+
+#[test]
+fn test_layer_flatten_event() {
+    let logs = common::capture_output(
+        |writer| {
+            Registry::default().with(
+                LogArgsLayer::new()
+                    .flatten_event(true)
+                    .with_writer(move || writer.clone()),
+            )
+        },
+        || {
+            handle_request(7, "bob".to_string());
+        },
+    );
+    let log_json: Value = serde_json::from_str(logs.trim()).expect("Failed to parse log as JSON");
+
+    assert!(log_json["fields"].is_null());
+    assert_eq!(log_json["message"].as_str(), Some("Loading invoices"));
+    assert_eq!(log_json["company_id"].as_str(), Some("7"));
+}
+
+#[test]
+fn test_layer_without_context() {
+    let logs = common::capture_output(
+        |writer| Registry::default().with(LogArgsLayer::new().with_writer(move || writer.clone())),
+        load_invoices,
+    );
+    let log_json: Value = serde_json::from_str(logs.trim()).expect("Failed to parse log as JSON");
+
+    assert_eq!(log_json["fields"]["count"].as_i64(), Some(3));
+    assert!(log_json["fields"]["company_id"].is_null());
+}
+
+#[test]
+fn test_context_layer_injects_into_plain_tracing_events() {
+    let logs = common::capture_output(
+        |writer| {
+            Registry::default().with(ContextLayer::new(
+                fmt::layer().json().with_writer(move || writer.clone()),
+            ))
+        },
+        || {
+            handle_request(42, "alice".to_string());
+        },
+    );
+    let log_json: Value = serde_json::from_str(logs.trim()).expect("Failed to parse log as JSON");
+
+    assert_eq!(log_json["level"].as_str(), Some("INFO"));
+    assert_eq!(
+        log_json["fields"]["message"].as_str(),
+        Some("Loading invoices")
+    );
+    assert_eq!(log_json["fields"]["count"].as_i64(), Some(3));
+    assert_eq!(log_json["fields"]["company_id"].as_str(), Some("42"));
+    assert_eq!(log_json["fields"]["user_id"].as_str(), Some("\"alice\""));
+}
+
+#[test]
+fn test_context_layer_keeps_event_fields_and_single_emission() {
+    let logs = common::capture_output(
+        |writer| {
+            Registry::default().with(ContextLayer::new(
+                fmt::layer().json().with_writer(move || writer.clone()),
+            ))
+        },
+        || {
+            handle_retry(9);
+        },
+    );
+    let lines: Vec<Value> = logs
+        .trim()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Failed to parse log as JSON"))
+        .collect();
+    assert_eq!(lines.len(), 2);
+
+    // Fields added by the decorated function's own macros are not duplicated
+    assert_eq!(lines[0]["fields"]["message"].as_str(), Some("Handling retry"));
+    assert_eq!(lines[0]["fields"]["company_id"].as_str(), Some("9"));
+
+    // Fields on the event win over the inherited context
+    assert_eq!(lines[1]["level"].as_str(), Some("WARN"));
+    assert_eq!(lines[1]["fields"]["company_id"].as_i64(), Some(0));
+}
+
+#[test]
+fn test_context_layer_without_context() {
+    let logs = common::capture_output(
+        |writer| {
+            Registry::default().with(ContextLayer::new(
+                fmt::layer().json().with_writer(move || writer.clone()),
+            ))
+        },
+        load_invoices,
+    );
+    let log_json: Value = serde_json::from_str(logs.trim()).expect("Failed to parse log as JSON");
+
+    assert_eq!(log_json["fields"]["count"].as_i64(), Some(3));
+    assert!(log_json["fields"]["company_id"].is_null());
+}
@@ -0,0 +1,68 @@
+//! Tests for the limits on context keys and callsites cached by `log_with_context!`
+//!
+//! The limits are process-wide, so this file holds a single test.
+
+mod common;
+
+use common::capture_logs;
+use log_args_runtime::{MAX_CONTEXT_CALLSITES, MAX_CONTEXT_KEYS};
+use std::collections::HashMap;
+
+fn log_keys(keys: &[String]) {
+    let context: HashMap<String, String> = keys
+        .iter()
+        .map(|key| (key.clone(), "v".to_string()))
+        .collect();
+    log_args_runtime::log_with_context!(tracing::info, context, "With context");
+}
+
+#[test]
+fn test_context_past_the_limits_is_folded_into_one_field() {
+    // Every key past `MAX_CONTEXT_KEYS` is logged in the `context` field
+    let keys: Vec<String> = (0..MAX_CONTEXT_KEYS + 10)
+        .map(|i| format!("key{}", i))
+        .collect();
+    let logs = capture_logs(|| {
+        for key in &keys {
+            log_keys(std::slice::from_ref(key));
+        }
+    });
+    assert_eq!(logs.len(), keys.len());
+    for (key, log) in keys.iter().zip(&logs).take(MAX_CONTEXT_KEYS) {
+        assert_eq!(log["fields"][key].as_str(), Some("v"));
+        assert!(log["fields"]["context"].is_null());
+    }
+    for (key, log) in keys.iter().zip(&logs).skip(MAX_CONTEXT_KEYS) {
+        assert!(log["fields"][key].is_null());
+        assert_eq!(
+            log["fields"]["context"].as_str(),
+            Some(format!("{{\"{}\": \"v\"}}", key).as_str())
+        );
+        assert_eq!(log["fields"]["message"].as_str(), Some("With context"));
+    }
+
+    // Each key set above made a callsite; new pairs of known keys get their own until
+    // `MAX_CONTEXT_CALLSITES` is reached
+    let pairs: Vec<[String; 2]> = (0..100)
+        .flat_map(|i| (i + 1..100).map(move |j| [format!("key{}", i), format!("key{}", j)]))
+        .collect();
+    let logs = capture_logs(|| {
+        for pair in &pairs {
+            log_keys(pair);
+        }
+    });
+    let remaining = MAX_CONTEXT_CALLSITES - MAX_CONTEXT_KEYS;
+    assert!(pairs.len() > remaining);
+    for ([first, second], log) in pairs.iter().zip(&logs).take(remaining) {
+        assert_eq!(log["fields"][first].as_str(), Some("v"));
+        assert_eq!(log["fields"][second].as_str(), Some("v"));
+        assert!(log["fields"]["context"].is_null());
+    }
+    for ([first, second], log) in pairs.iter().zip(&logs).skip(remaining) {
+        assert!(log["fields"][first].is_null());
+        assert_eq!(
+            log["fields"]["context"].as_str(),
+            Some(format!("{{\"{}\": \"v\", \"{}\": \"v\"}}", first, second).as_str())
+        );
+    }
+}
@@ -0,0 +1,98 @@
+//! Tests for the deterministic, escaped context string encoding
+
+use log_args::params;
+use log_args_runtime::{
+    format_context_string, get_inherited_context_string, parse_context_string, NO_CONTEXT,
+};
+use std::collections::HashMap;
+
+fn context(entries: &[(&str, &str)]) -> HashMap<String, String> {
+    entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_entries_are_sorted_by_key() {
+    let encoded = format_context_string(&context(&[
+        ("user_id", "7"),
+        ("company_id", "42"),
+        ("request_id", "abc"),
+    ]));
+
+    assert_eq!(encoded, "company_id=42,request_id=abc,user_id=7");
+}
+
+#[test]
+fn test_separators_quotes_and_newlines_are_escaped() {
+    let encoded = format_context_string(&context(&[
+        ("query", "a=1,b=\"two\""),
+        ("note", "line one\nline two\\end"),
+    ]));
+
+    assert_eq!(
+        encoded,
+        r#"note=line one\nline two\\end,query=a\=1\,b\=\"two\""#
+    );
+}
+
+#[test]
+fn test_round_trip() {
+    let original = context(&[
+        ("plain", "value"),
+        ("commas", "a,b,,c"),
+        ("k=ey", "v=alue"),
+        ("quotes", "\"quoted\""),
+        ("whitespace", "tab\there\r\nnext"),
+        ("backslash", "C:\\path\\"),
+        ("empty", ""),
+        ("unicode", "café ☕"),
+    ]);
+
+    let parsed: HashMap<String, String> = parse_context_string(&format_context_string(&original))
+        .unwrap()
+        .into_iter()
+        .collect();
+
+    assert_eq!(parsed, original);
+}
+
+#[test]
+fn test_empty_context() {
+    assert_eq!(format_context_string(&HashMap::new()), NO_CONTEXT);
+    assert_eq!(parse_context_string(NO_CONTEXT).unwrap(), vec![]);
+    assert_eq!(parse_context_string("").unwrap(), vec![]);
+}
+
+#[test]
+fn test_malformed_strings_are_rejected() {
+    assert!(parse_context_string("key").is_err());
+    assert!(parse_context_string("a=1,b").is_err());
+    assert!(parse_context_string("a=1=2").is_err());
+    assert!(parse_context_string("a=1\\").is_err());
+
+    let error = parse_context_string("a=\\x").unwrap_err();
+    assert_eq!(error.position(), 2);
+    assert_eq!(error.to_string(), "unknown escape `\\x` at byte 2");
+}
+
+#[params(fields(tenant, user_id), custom(service = "billing,eu"))]
+fn inherited_context_string(tenant: String, user_id: u64) -> String {
+    get_inherited_context_string()
+}
+
+#[test]
+fn test_inherited_context_string_is_deterministic() {
+    let encoded = inherited_context_string("acme".to_string(), 7);
+
+    assert_eq!(encoded, r#"service=billing\,eu,tenant=\"acme\",user_id=7"#);
+    assert_eq!(
+        parse_context_string(&encoded).unwrap(),
+        vec![
+            ("service".to_string(), "billing,eu".to_string()),
+            ("tenant".to_string(), "\"acme\"".to_string()),
+            ("user_id".to_string(), "7".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,90 @@
+//! Tests for the ECS output profile
+
+mod common;
+
+use log_args::params;
+use log_args_runtime::{EcsLayer, EcsMapping};
+use serde_json::Value;
+use tracing_subscriber::{prelude::*, Registry};
+
+fn capture_ecs<F: FnOnce()>(mapping: EcsMapping, f: F) -> Vec<Value> {
+    let output = common::capture_output(
+        |writer| {
+            Registry::default().with(
+                EcsLayer::new()
+                    .with_mapping(mapping)
+                    .with_writer(move || writer.clone()),
+            )
+        },
+        f,
+    );
+    common::parse_json_lines(&output)
+}
+
+fn charge_card() {
+    tracing::info!(user.id = 7, amount = 100, "Charging card");
+}
+
+#[params(fields(company_id))]
+fn handle_payment(company_id: u64) {
+    charge_card();
+}
+
+#[test]
+fn test_standard_keys_are_renamed() {
+    let logs = capture_ecs(EcsMapping::new(), || {
+        tracing::warn!(function = "retry_payment", "Retrying")
+    });
+    assert_eq!(logs.len(), 1);
+
+    let log = &logs[0];
+    assert!(log["@timestamp"].is_string());
+    assert_eq!(log["message"].as_str(), Some("Retrying"));
+    assert_eq!(log["log"]["level"].as_str(), Some("warn"));
+    assert_eq!(log["log"]["logger"].as_str(), Some("ecs_tests"));
+    assert_eq!(
+        log["log"]["origin"]["function"].as_str(),
+        Some("retry_payment")
+    );
+    assert!(log["log"]["origin"]["file"]["line"].is_u64());
+    assert_eq!(log["ecs"]["version"].as_str(), Some("8.11.0"));
+}
+
+#[test]
+fn test_dotted_keys_nest_and_context_goes_to_labels() {
+    let logs = capture_ecs(EcsMapping::new(), || handle_payment(42));
+    assert_eq!(logs.len(), 1);
+
+    let log = &logs[0];
+    assert_eq!(log["user"]["id"].as_i64(), Some(7));
+    assert_eq!(log["labels"]["amount"].as_i64(), Some(100));
+    assert_eq!(log["labels"]["company_id"].as_str(), Some("42"));
+    assert!(log["company_id"].is_null());
+}
+
+#[test]
+fn test_mapping_table_renames_custom_keys() {
+    let mapping = EcsMapping::new()
+        .rename("tenant", "organization.id")
+        .rename("message", "event.reason");
+    let logs = capture_ecs(mapping, || tracing::info!(tenant = "acme", "Provisioned"));
+    assert_eq!(logs.len(), 1);
+
+    let log = &logs[0];
+    assert_eq!(log["organization"]["id"].as_str(), Some("acme"));
+    assert_eq!(log["event"]["reason"].as_str(), Some("Provisioned"));
+    assert!(log["message"].is_null());
+    assert!(log["labels"]["tenant"].is_null());
+}
+
+#[test]
+fn test_conflicting_paths_fall_back_to_labels() {
+    let logs = capture_ecs(EcsMapping::new(), || {
+        tracing::info!(user = "alice", user.id = 7, "Conflict")
+    });
+    assert_eq!(logs.len(), 1);
+
+    let log = &logs[0];
+    assert_eq!(log["labels"]["user"].as_str(), Some("alice"));
+    assert_eq!(log["user"]["id"].as_i64(), Some(7));
+}
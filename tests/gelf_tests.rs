@@ -0,0 +1,131 @@
+//! Tests for the GELF output layer
+
+mod common;
+
+use log_args::params;
+use log_args_runtime::GelfLayer;
+use serde_json::Value;
+use std::io::Read;
+use std::net::{TcpListener, UdpSocket};
+use std::sync::Mutex;
+use tracing_subscriber::{prelude::*, Registry};
+
+/// Sends every write as one UDP datagram
+struct UdpWriter(UdpSocket);
+
+impl std::io::Write for UdpWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.send(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn capture_gelf<F: FnOnce()>(f: F) -> Vec<Value> {
+    let output = common::capture_output(
+        |writer| {
+            Registry::default().with(
+                GelfLayer::new()
+                    .with_host("api-1")
+                    .with_writer(move || writer.clone()),
+            )
+        },
+        f,
+    );
+
+    output
+        .split_terminator('\0')
+        .map(|message| serde_json::from_str(message).expect("Failed to parse GELF message"))
+        .collect()
+}
+
+fn charge_card() {
+    tracing::warn!(amount = 100, id = 9, "Charging card");
+}
+
+#[params(fields(company_id))]
+fn handle_payment(company_id: u64) {
+    charge_card();
+}
+
+#[test]
+fn test_gelf_message_includes_context_as_additional_fields() {
+    let messages = capture_gelf(|| handle_payment(42));
+    assert_eq!(messages.len(), 1);
+
+    let message = &messages[0];
+    assert_eq!(message["version"].as_str(), Some("1.1"));
+    assert_eq!(message["host"].as_str(), Some("api-1"));
+    assert_eq!(message["short_message"].as_str(), Some("Charging card"));
+    assert_eq!(message["level"].as_u64(), Some(4));
+    assert!(message["timestamp"].is_f64());
+    assert_eq!(message["_target"].as_str(), Some("gelf_tests"));
+    assert_eq!(message["_amount"].as_i64(), Some(100));
+    assert_eq!(message["__id"].as_i64(), Some(9));
+    assert_eq!(message["_company_id"].as_str(), Some("42"));
+    assert!(message["_id"].is_null());
+}
+
+#[test]
+fn test_gelf_function_key_and_field_names() {
+    let messages = capture_gelf(|| {
+        tracing::error!(function = "retry_payment", retried = true, "Retry failed")
+    });
+    assert_eq!(messages.len(), 1);
+
+    let message = &messages[0];
+    assert_eq!(message["level"].as_u64(), Some(3));
+    assert_eq!(message["_function"].as_str(), Some("retry_payment"));
+    // GELF only allows strings and numbers as additional field values
+    assert_eq!(message["_retried"].as_str(), Some("true"));
+}
+
+#[test]
+fn test_gelf_over_tcp_is_null_terminated() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let stream = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut accepted, _) = listener.accept().unwrap();
+
+    let subscriber = Registry::default().with(
+        GelfLayer::new()
+            .with_host("api-1")
+            .with_writer(Mutex::new(stream)),
+    );
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("First");
+        tracing::info!("Second");
+    });
+
+    let mut received = Vec::new();
+    accepted.read_to_end(&mut received).unwrap();
+    let messages: Vec<Value> = received
+        .split(|byte| *byte == 0)
+        .filter(|message| !message.is_empty())
+        .map(|message| serde_json::from_slice(message).unwrap())
+        .collect();
+    assert_eq!(received.last(), Some(&0));
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[1]["short_message"].as_str(), Some("Second"));
+}
+
+#[test]
+fn test_gelf_over_udp_sends_one_datagram_per_event() {
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender.connect(receiver.local_addr().unwrap()).unwrap();
+
+    let subscriber = Registry::default().with(
+        GelfLayer::new()
+            .with_delimiter(None)
+            .with_writer(Mutex::new(UdpWriter(sender))),
+    );
+    tracing::subscriber::with_default(subscriber, || tracing::info!(attempt = 1, "Over UDP"));
+
+    let mut datagram = [0u8; 65_536];
+    let len = receiver.recv(&mut datagram).unwrap();
+    let message: Value = serde_json::from_slice(&datagram[..len]).unwrap();
+    assert_eq!(message["short_message"].as_str(), Some("Over UDP"));
+    assert_eq!(message["_attempt"].as_i64(), Some(1));
+}
@@ -1,38 +1,18 @@
+mod common;
+
+use common::MockWriter;
 use log_args::params;
 use serde_json::Value;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tracing::info;
 use tracing_subscriber::{fmt, prelude::*, Registry};
 
-/// A mock writer that captures logs into a shared buffer for testing
-#[derive(Clone)]
-struct MockWriter {
-    buf: Arc<Mutex<Vec<u8>>>,
-}
-
-impl MockWriter {
-    fn new() -> Self {
-        Self {
-            buf: Arc::new(Mutex::new(Vec::new())),
-        }
-    }
-
-    fn get_logs(&self) -> String {
-        let mut buf = self.buf.lock().unwrap();
-        let output = String::from_utf8_lossy(&buf).to_string();
-        buf.clear();
-        output
-    }
-}
-
-impl std::io::Write for MockWriter {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.buf.lock().unwrap().write(buf)
-    }
-
-    fn flush(&mut self) -> std::io::Result<()> {
-        self.buf.lock().unwrap().flush()
-    }
+/// Parse the first JSON log line carrying the given message
+fn find_log(logs: &str, message: &str) -> Value {
+    logs.lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("Failed to parse log as JSON"))
+        .find(|log| log["fields"]["message"].as_str() == Some(message))
+        .unwrap_or_else(|| panic!("No log with message {:?}", message))
 }
 
 #[derive(Debug, Clone)]
@@ -97,6 +77,7 @@ fn test_fields_attribute() {
     tracing::subscriber::with_default(subscriber, || {
         #[params(fields(user.id, user.name, config.debug))]
         fn test_function(user: TestUser, config: TestConfig, _secret: String) {
+            let _ = config.timeout;
             info!("Selective logging test");
         }
 
@@ -547,3 +528,234 @@ fn test_error_handling_preserves_context() {
     assert_eq!(log2["fields"]["message"].as_str(), Some("Operation failed"));
     assert_eq!(log2["level"].as_str(), Some("ERROR"));
 }
+
+#[test]
+fn test_all_skips_types() {
+    let writer = MockWriter::new();
+    let writer_clone = writer.clone();
+    let subscriber = Registry::default().with(
+        fmt::layer()
+            .json()
+            .with_writer(move || writer_clone.clone()),
+    );
+
+    #[derive(Debug)]
+    struct Token(&'static str);
+
+    #[derive(Debug)]
+    struct PgPool;
+
+    tracing::subscriber::with_default(subscriber, || {
+        #[params(all)]
+        fn default_skip(token: Token, user_id: u64) {
+            let _ = token.0;
+            info!("Default skip test");
+        }
+
+        #[params(all, skip_types(PgPool), allow_sensitive(token))]
+        fn explicit_skip(pool: &PgPool, token: Arc<Token>, user_id: u64) {
+            let _ = (pool, token);
+            info!("Explicit skip test");
+        }
+
+        default_skip(Token("secret-token"), 7);
+        explicit_skip(&PgPool, Arc::new(Token("other-token")), 8);
+    });
+
+    let logs = writer.get_logs();
+    let lines: Vec<&str> = logs.trim().split('\n').collect();
+    let log1: Value = serde_json::from_str(lines[0]).expect("Failed to parse first log");
+    let log2: Value = serde_json::from_str(lines[1]).expect("Failed to parse second log");
+
+    // The default deny-list skips Token
+    assert!(log1["fields"]["token"].is_null());
+    assert_eq!(log1["fields"]["user_id"].as_str(), Some("7"));
+
+    // An explicit list replaces the default, matching through references
+    assert!(log2["fields"]["pool"].is_null());
+    assert!(log2["fields"]["token"]
+        .as_str()
+        .unwrap()
+        .contains("other-token"));
+    assert_eq!(log2["fields"]["user_id"].as_str(), Some("8"));
+}
+
+#[test]
+fn test_all_with_skip_and_redact() {
+    let writer = MockWriter::new();
+    let writer_clone = writer.clone();
+    let subscriber = Registry::default().with(
+        fmt::layer()
+            .json()
+            .with_writer(move || writer_clone.clone()),
+    );
+
+    tracing::subscriber::with_default(subscriber, || {
+        #[params(all, strict, skip(session_token), redact(password))]
+        fn test_function(username: String, password: String, session_token: String) {
+            let _ = (password, session_token);
+            info!("Sensitive parameters test");
+        }
+
+        test_function(
+            "alice".to_string(),
+            "hunter2".to_string(),
+            "abc123".to_string(),
+        );
+    });
+
+    let logs = writer.get_logs();
+    let log_json: Value = serde_json::from_str(&logs).expect("Failed to parse log as JSON");
+
+    assert_eq!(log_json["fields"]["username"].as_str(), Some("\"alice\""));
+    assert_eq!(log_json["fields"]["password"].as_str(), Some("[REDACTED]"));
+    assert!(log_json["fields"]["session_token"].is_null());
+    assert!(!logs.contains("hunter2"));
+    assert!(!logs.contains("abc123"));
+}
+
+#[test]
+fn test_hashed_fields() {
+    let writer = MockWriter::new();
+    let writer_clone = writer.clone();
+    let subscriber = Registry::default().with(
+        fmt::layer()
+            .json()
+            .with_writer(move || writer_clone.clone()),
+    );
+
+    log_args_runtime::init_hash_key(b"integration-test-key");
+
+    tracing::subscriber::with_default(subscriber, || {
+        #[params(fields(user.id), hash(user.email))]
+        fn test_function(user: TestUser) {
+            info!("Hashed fields test");
+        }
+
+        test_function(TestUser {
+            id: 321,
+            name: "Grace".to_string(),
+            email: "grace@test.com".to_string(),
+        });
+    });
+
+    let logs = writer.get_logs();
+    let log_json = find_log(&logs, "Hashed fields test");
+    let hash = log_json["fields"]["user.email"].as_str().unwrap();
+
+    // The raw email never appears, only its stable keyed hash
+    assert!(!logs.contains("grace@test.com"));
+    assert_eq!(hash.len(), 32);
+    assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(
+        hash,
+        log_args_runtime::hash_value(&format!("{:?}", "grace@test.com"))
+    );
+    assert_ne!(hash, log_args_runtime::hash_value(&format!("{:?}", "other@test.com")));
+}
+
+#[test]
+fn test_context_logged_once_as_individual_fields() {
+    let writer = MockWriter::new();
+    let writer_clone = writer.clone();
+    let subscriber = Registry::default().with(
+        fmt::layer()
+            .json()
+            .with_writer(move || writer_clone.clone()),
+    );
+
+    tracing::subscriber::with_default(subscriber, || {
+        #[params(fields(company_id), custom(service = "billing"))]
+        fn parent_function(company_id: u64) {
+            info!("Parent message");
+            child_function();
+        }
+
+        fn child_function() {
+            log_args_runtime::info!(attempt = 2, service = "override", "Child message");
+        }
+
+        parent_function(42);
+    });
+
+    let logs = writer.get_logs();
+    let lines: Vec<&str> = logs.trim().split('\n').collect();
+
+    // One event per log call, no duplicate fallback events
+    assert_eq!(lines.len(), 2);
+
+    let parent_log = find_log(&logs, "Parent message");
+    assert_eq!(parent_log["fields"]["company_id"].as_str(), Some("42"));
+    assert_eq!(parent_log["fields"]["service"].as_str(), Some("billing"));
+    assert!(parent_log["fields"]["context"].is_null());
+
+    // Inherited context arrives as individual fields; explicit fields win
+    let child_log = find_log(&logs, "Child message");
+    assert_eq!(child_log["fields"]["company_id"].as_str(), Some("42"));
+    assert_eq!(child_log["fields"]["attempt"].as_i64(), Some(2));
+    assert_eq!(child_log["fields"]["service"].as_str(), Some("override"));
+    assert!(child_log["fields"]["context"].is_null());
+}
+
+#[test]
+fn test_bunyan_output_includes_context() {
+    let writer = MockWriter::new();
+    let writer_clone = writer.clone();
+    let subscriber = Registry::default().with(log_args_runtime::ContextLayer::bunyan(
+        "billing",
+        move || writer_clone.clone(),
+    ));
+
+    tracing::subscriber::with_default(subscriber, || {
+        #[params(fields(company_id, user_id))]
+        fn bunyan_parent(company_id: u64, user_id: u64) {
+            info!("Parent message");
+            bunyan_child();
+        }
+
+        fn bunyan_child() {
+            tracing::info!(attempt = 1, "Child message");
+        }
+
+        bunyan_parent(42, 7);
+    });
+
+    let logs = writer.get_logs();
+    let lines: Vec<&str> = logs.trim().lines().collect();
+    assert_eq!(lines.len(), 2);
+
+    let parent_log: Value = serde_json::from_str(lines[0]).expect("Failed to parse log as JSON");
+    assert_eq!(parent_log["name"].as_str(), Some("billing"));
+    assert_eq!(parent_log["msg"].as_str(), Some("Parent message"));
+    assert_eq!(parent_log["company_id"].as_str(), Some("42"));
+    assert_eq!(parent_log["user_id"].as_str(), Some("7"));
+    // Each property is written once, not again from the inherited context
+    assert_eq!(lines[0].matches("\"company_id\"").count(), 1);
+
+    // Plain tracing calls get the inherited context as top-level properties too
+    let child_log: Value = serde_json::from_str(lines[1]).expect("Failed to parse log as JSON");
+    assert_eq!(child_log["msg"].as_str(), Some("Child message"));
+    assert_eq!(child_log["attempt"].as_i64(), Some(1));
+    assert_eq!(child_log["company_id"].as_str(), Some("42"));
+    assert_eq!(child_log["user_id"].as_str(), Some("7"));
+}
+
+#[test]
+fn test_bunyan_output_without_context() {
+    let writer = MockWriter::new();
+    let writer_clone = writer.clone();
+    let subscriber = Registry::default().with(log_args_runtime::ContextLayer::bunyan(
+        "billing",
+        move || writer_clone.clone(),
+    ));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::warn!("Nothing inherited");
+    });
+
+    let logs = writer.get_logs();
+    let log: Value = serde_json::from_str(logs.trim()).expect("Failed to parse log as JSON");
+    assert_eq!(log["msg"].as_str(), Some("Nothing inherited"));
+    assert_eq!(log["level"].as_u64(), Some(40));
+    assert!(log["company_id"].is_null());
+}
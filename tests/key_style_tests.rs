@@ -0,0 +1,86 @@
+//! Tests for `key_style` and `prefix`, which rename emitted fields and propagated
+//! context keys
+
+mod common;
+
+use common::capture_logs;
+use log_args::params;
+
+struct User {
+    id: u64,
+}
+
+#[params(fields(user.id), key_style = "snake", prefix = "args")]
+fn snake_with_prefix(user: User) {
+    info!("Snake keys");
+}
+
+#[test]
+fn test_snake_style_with_prefix() {
+    let logs = capture_logs(|| snake_with_prefix(User { id: 7 }));
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0]["fields"]["args.user_id"].as_str(), Some("7"));
+    assert!(logs[0]["fields"]["user.id"].is_null());
+}
+
+#[params(fields(user.id), custom(request_source = "api"), key_style = "camel", prefix = "args")]
+fn camel_with_prefix(user: User) {
+    info!("Camel keys");
+}
+
+#[test]
+fn test_camel_style_with_prefix() {
+    let logs = capture_logs(|| camel_with_prefix(User { id: 8 }));
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0]["fields"]["argsUserId"].as_str(), Some("8"));
+    assert_eq!(logs[0]["fields"]["argsRequestSource"].as_str(), Some("api"));
+}
+
+#[params(all, key_style = "camel")]
+fn camel_all(order_id: u64, retry_count: u32) {
+    info!("All parameters");
+}
+
+#[test]
+fn test_camel_style_applies_to_all_parameters() {
+    let logs = capture_logs(|| camel_all(1, 2));
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0]["fields"]["orderId"].as_str(), Some("1"));
+    assert_eq!(logs[0]["fields"]["retryCount"].as_str(), Some("2"));
+    assert!(logs[0]["fields"]["order_id"].is_null());
+}
+
+#[params(fields(user.id), prefix = "args")]
+fn prefix_only(user: User) {
+    info!("Prefix only");
+    load_profile();
+}
+
+#[params]
+fn load_profile() {
+    info!("Loading profile");
+}
+
+#[test]
+fn test_prefixed_keys_propagate_to_children() {
+    let logs = capture_logs(|| prefix_only(User { id: 9 }));
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[0]["fields"]["args.user.id"].as_str(), Some("9"));
+    assert_eq!(
+        logs[1]["fields"]["message"].as_str(),
+        Some("Loading profile")
+    );
+    assert_eq!(logs[1]["fields"]["args.user.id"].as_str(), Some("9"));
+}
+
+#[test]
+fn test_context_map_uses_styled_keys() {
+    let context = std::cell::RefCell::new(None);
+    #[params(fields(user.id), key_style = "snake")]
+    fn read_context(user: User, context: &std::cell::RefCell<Option<String>>) {
+        *context.borrow_mut() = log_args_runtime::get_context_value("user_id");
+    }
+
+    read_context(User { id: 3 }, &context);
+    assert_eq!(context.into_inner().as_deref(), Some("3"));
+}
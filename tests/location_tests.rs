@@ -0,0 +1,82 @@
+//! Tests for `location`, which logs where the function is defined and optionally
+//! where each log macro is invoked
+
+mod common;
+
+use common::capture_logs;
+use log_args::params;
+
+const DEFINITION_LINE: u32 = line!() + 2;
+#[params(location)]
+fn load_invoices() {
+    info!("Loading invoices");
+    render_invoice();
+}
+
+fn render_invoice() {
+    log_args_runtime::info!("Rendering invoice");
+}
+
+#[test]
+fn test_location_of_definition() {
+    let logs = capture_logs(load_invoices);
+    assert_eq!(logs.len(), 2);
+
+    let fields = &logs[0]["fields"];
+    assert_eq!(
+        fields["location.module_path"].as_str(),
+        Some("location_tests")
+    );
+    assert_eq!(fields["location.file"].as_str(), Some(file!()));
+    let line = DEFINITION_LINE.to_string();
+    assert_eq!(fields["location.line"].as_str(), Some(line.as_str()));
+    assert!(fields["line"].is_null());
+    assert!(fields["call_line"].is_null());
+
+    // The undecorated child inherits the same values under the same keys
+    let child = &logs[1]["fields"];
+    assert_eq!(child["location.file"], fields["location.file"]);
+    assert_eq!(child["location.line"], fields["location.line"]);
+}
+
+#[test]
+fn test_location_is_propagated_with_context() {
+    let _guard = log_args_runtime::push_context(Default::default());
+    let mut inherited = None;
+    #[params(location)]
+    fn outer(inherited: &mut Option<String>) {
+        *inherited = log_args_runtime::get_context_value("location.line");
+    }
+    outer(&mut inherited);
+
+    let line = inherited.expect("line should be in the context");
+    assert!(line.parse::<u32>().is_ok());
+}
+
+#[params(location(call_site))]
+fn ship_order() {
+    let expected = line!() + 1;
+    info!(expected_line = expected, "Shipping");
+    let expected = line!() + 1;
+    tracing::warn!(expected_line = expected, "Carrier delayed");
+}
+
+#[test]
+fn test_call_site_of_each_log_macro() {
+    let logs = capture_logs(ship_order);
+    assert_eq!(logs.len(), 2);
+
+    for log in &logs {
+        let fields = &log["fields"];
+        assert_eq!(fields["call_file"].as_str(), Some(file!()));
+        assert_eq!(
+            fields["call_line"].as_u64(),
+            fields["expected_line"].as_u64()
+        );
+        assert!(fields["location.line"].as_str().is_some());
+    }
+    assert_ne!(
+        logs[0]["fields"]["call_line"],
+        logs[1]["fields"]["call_line"]
+    );
+}
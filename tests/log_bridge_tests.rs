@@ -0,0 +1,183 @@
+//! Tests for the `log` crate bridge: `ContextLogger` and `#[params(log_compat)]`
+
+use log::kv::{Key, Value, VisitSource};
+use log::{Log, Metadata, Record};
+use log_args::params;
+use log_args_runtime::ContextLogger;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, Once};
+
+/// A `log` record reduced to what the tests check
+#[derive(Debug, Clone)]
+struct CapturedRecord {
+    level: log::Level,
+    target: String,
+    message: String,
+    key_values: BTreeMap<String, String>,
+}
+
+/// A logger that keeps every record in memory
+struct CaptureLogger;
+
+static RECORDS: Mutex<Vec<CapturedRecord>> = Mutex::new(Vec::new());
+static INIT: Once = Once::new();
+
+impl Log for CaptureLogger {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let mut collector = KeyValues::default();
+        record.key_values().visit(&mut collector).unwrap();
+        RECORDS.lock().unwrap().push(CapturedRecord {
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+            key_values: collector.0,
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+#[derive(Default)]
+struct KeyValues(BTreeMap<String, String>);
+
+impl<'kvs> VisitSource<'kvs> for KeyValues {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+        self.0.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
+
+// Records from tests running in parallel share one global logger, so each test
+// looks its records up by message
+fn find_record(message: &str) -> CapturedRecord {
+    RECORDS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|record| record.message == message)
+        .cloned()
+        .unwrap_or_else(|| panic!("No record with message {:?}", message))
+}
+
+fn init_logger() {
+    INIT.call_once(|| {
+        ContextLogger::new(CaptureLogger)
+            .init(log::LevelFilter::Trace)
+            .unwrap();
+    });
+}
+
+// Undecorated helper that only knows the `log` facade
+fn legacy_helper() {
+    log::info!(attempt = 1; "Legacy helper running");
+}
+
+#[params(fields(order_id))]
+fn handle_order(order_id: u64) {
+    legacy_helper();
+}
+
+#[test]
+fn test_context_logger_adds_context_key_values() {
+    init_logger();
+    handle_order(42);
+
+    let record = find_record("Legacy helper running");
+    assert_eq!(record.level, log::Level::Info);
+    assert_eq!(record.key_values.get("order_id").map(String::as_str), Some("42"));
+    assert_eq!(record.key_values.get("attempt").map(String::as_str), Some("1"));
+}
+
+#[params(fields(order_id), custom(service = "shipping"), log_compat)]
+fn ship_order(order_id: u64) {
+    info!("Shipping order {}", order_id);
+    warn!(target: "carrier", carrier = "ups"; "Carrier delayed");
+    log::debug!("Label printed");
+}
+
+#[test]
+fn test_log_compat_mode_adds_fields_as_key_values() {
+    init_logger();
+    ship_order(7);
+
+    let record = find_record("Shipping order 7");
+    assert_eq!(record.level, log::Level::Info);
+    assert_eq!(record.key_values.get("order_id").map(String::as_str), Some("7"));
+    assert_eq!(record.key_values.get("service").map(String::as_str), Some("shipping"));
+
+    let record = find_record("Carrier delayed");
+    assert_eq!(record.level, log::Level::Warn);
+    assert_eq!(record.target, "carrier");
+    assert_eq!(record.key_values.get("carrier").map(String::as_str), Some("ups"));
+    assert_eq!(record.key_values.get("order_id").map(String::as_str), Some("7"));
+
+    let record = find_record("Label printed");
+    assert_eq!(record.level, log::Level::Debug);
+    assert_eq!(record.key_values.get("order_id").map(String::as_str), Some("7"));
+}
+
+struct Customer {
+    id: u64,
+    tier: String,
+}
+
+#[params(fields(customer.id, customer.tier), log_compat)]
+fn load_customer(customer: Customer) {
+    info!("request.path" = "/customers"; "Loading customer");
+}
+
+#[params(fields(order_id), prefix = "shipping", log_compat)]
+fn track_order(order_id: u64) {
+    info!("Tracking order");
+}
+
+#[test]
+fn test_log_compat_mode_keeps_dotted_keys() {
+    init_logger();
+    load_customer(Customer {
+        id: 9,
+        tier: "gold".to_string(),
+    });
+    track_order(3);
+
+    let record = find_record("Loading customer");
+    assert_eq!(record.key_values.get("customer.id").map(String::as_str), Some("9"));
+    // Fields are captured with `Debug`, as they are for `tracing`
+    assert_eq!(
+        record.key_values.get("customer.tier").map(String::as_str),
+        Some("\"gold\"")
+    );
+    assert_eq!(
+        record.key_values.get("request.path").map(String::as_str),
+        Some("/customers")
+    );
+
+    let record = find_record("Tracking order");
+    assert_eq!(
+        record.key_values.get("shipping.order_id").map(String::as_str),
+        Some("3")
+    );
+}
+
+#[test]
+fn test_record_key_values_win_over_context() {
+    init_logger();
+
+    #[params(fields(order_id))]
+    fn override_order(order_id: u64) {
+        helper();
+    }
+
+    fn helper() {
+        log::info!(order_id = 0; "Overridden order id");
+    }
+
+    override_order(9);
+
+    let record = find_record("Overridden order id");
+    assert_eq!(record.key_values.get("order_id").map(String::as_str), Some("0"));
+}
@@ -0,0 +1,170 @@
+//! Tests for `log_with_context!` call sites: arguments are evaluated where the call is,
+//! with the caller's dispatcher, and the replayed event keeps its explicit parent
+
+mod common;
+
+use common::capture_logs;
+use log_args::params;
+use serde_json::Value;
+use std::collections::HashMap;
+
+fn context() -> HashMap<String, String> {
+    let mut context = HashMap::new();
+    context.insert("tenant".to_string(), "acme".to_string());
+    context
+}
+
+fn find_log<'a>(logs: &'a [Value], message: &str) -> &'a Value {
+    logs.iter()
+        .find(|log| log["fields"]["message"].as_str() == Some(message))
+        .unwrap_or_else(|| panic!("no log with message {:?}", message))
+}
+
+fn compute() -> u64 {
+    tracing::warn!("Computing");
+    7
+}
+
+#[test]
+fn test_events_logged_by_arguments_are_kept() {
+    let logs = capture_logs(|| {
+        log_args_runtime::log_with_context!(
+            tracing::info,
+            context(),
+            value = compute(),
+            "Computed"
+        );
+    });
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[0]["fields"]["message"].as_str(), Some("Computing"));
+    assert_eq!(logs[1]["fields"]["message"].as_str(), Some("Computed"));
+    assert_eq!(logs[1]["fields"]["value"].as_u64(), Some(7));
+    assert_eq!(logs[1]["fields"]["tenant"].as_str(), Some("acme"));
+    assert!(logs[1]["fields"]["__log_args_capture"].is_null());
+}
+
+fn parse_and_log(input: &str) -> Result<(), std::num::ParseIntError> {
+    log_args_runtime::log_with_context!(
+        tracing::info,
+        context(),
+        value = input.parse::<u64>()?,
+        "Parsed {}",
+        input
+    );
+    Ok(())
+}
+
+#[test]
+fn test_question_mark_in_arguments_returns_from_the_caller() {
+    let logs = capture_logs(|| {
+        assert!(parse_and_log("7").is_ok());
+        assert!(parse_and_log("seven").is_err());
+    });
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0]["fields"]["message"].as_str(), Some("Parsed 7"));
+    assert_eq!(logs[0]["fields"]["value"].as_u64(), Some(7));
+    assert_eq!(logs[0]["fields"]["tenant"].as_str(), Some("acme"));
+}
+
+async fn fetch() -> u64 {
+    tokio::task::yield_now().await;
+    tracing::warn!("Fetching");
+    42
+}
+
+#[test]
+fn test_await_in_arguments() {
+    let logs = capture_logs(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            log_args_runtime::log_with_context!(
+                tracing::info,
+                context(),
+                value = fetch().await,
+                "Fetched"
+            );
+        });
+    });
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[0]["fields"]["message"].as_str(), Some("Fetching"));
+    assert_eq!(logs[1]["fields"]["value"].as_u64(), Some(42));
+    assert_eq!(logs[1]["fields"]["tenant"].as_str(), Some("acme"));
+}
+
+#[test]
+fn test_explicit_parent_is_kept() {
+    let logs = capture_logs(|| {
+        let request = tracing::info_span!("request");
+        let _current = tracing::info_span!("current").entered();
+        log_args_runtime::log_with_context!(tracing::info, context(), parent: &request, "Child of request");
+        log_args_runtime::log_with_context!(tracing::info, context(), "Contextual event");
+    });
+    assert_eq!(logs.len(), 2);
+
+    let child = find_log(&logs, "Child of request");
+    assert_eq!(child["span"]["name"].as_str(), Some("request"));
+    assert_eq!(child["fields"]["tenant"].as_str(), Some("acme"));
+
+    let contextual = find_log(&logs, "Contextual event");
+    assert_eq!(contextual["span"]["name"].as_str(), Some("current"));
+}
+
+#[params(custom(tenant = "acme"))]
+async fn handle_request() {
+    info!("Handling request");
+    load_account().await;
+}
+
+#[params]
+async fn load_account() {
+    info!("Loading account");
+    log_args_runtime::info!("Loaded account");
+}
+
+#[test]
+fn test_async_children_inherit_the_parent_context() {
+    let logs = capture_logs(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(handle_request());
+    });
+    assert_eq!(logs.len(), 3);
+    for log in &logs {
+        assert_eq!(log["fields"]["tenant"].as_str(), Some("acme"));
+    }
+}
+
+#[test]
+fn test_tracing_argument_forms() {
+    let user = "alice";
+    let id = 7;
+    let logs = capture_logs(|| {
+        log_args_runtime::log_with_context!(
+            tracing::info,
+            context(),
+            target: "audit",
+            user,
+            ?id,
+            %user,
+            request.size = 3,
+            "kind" = ?"upload",
+            "Logged {} at {}",
+            user,
+            id
+        );
+        log_args_runtime::log_with_context!(tracing::info, context(), id);
+    });
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[0]["target"].as_str(), Some("audit"));
+    assert_eq!(logs[0]["fields"]["message"].as_str(), Some("Logged alice at 7"));
+    assert_eq!(logs[0]["fields"]["user"].as_str(), Some("alice"));
+    assert_eq!(logs[0]["fields"]["id"].as_str(), Some("7"));
+    assert_eq!(logs[0]["fields"]["request.size"].as_u64(), Some(3));
+    assert_eq!(logs[0]["fields"]["kind"].as_str(), Some("\"upload\""));
+    assert_eq!(logs[0]["fields"]["tenant"].as_str(), Some("acme"));
+    assert_eq!(logs[1]["fields"]["id"].as_u64(), Some(7));
+    assert_eq!(logs[1]["fields"]["tenant"].as_str(), Some("acme"));
+}
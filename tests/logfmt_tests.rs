@@ -0,0 +1,88 @@
+//! Tests for the logfmt event formatter
+
+mod common;
+
+use log_args::params;
+use log_args_runtime::LogfmtFormat;
+use tracing_subscriber::{fmt, prelude::*, Registry};
+
+fn capture_logfmt<F: FnOnce()>(format: LogfmtFormat, f: F) -> Vec<String> {
+    let output = common::capture_output(
+        |writer| {
+            Registry::default().with(
+                fmt::layer()
+                    .event_format(format)
+                    .with_writer(move || writer.clone()),
+            )
+        },
+        f,
+    );
+    output.lines().map(str::to_string).collect()
+}
+
+fn charge_card() {
+    tracing::info!(amount = 100, note = "first try", "Charging card");
+}
+
+#[params(fields(company_id, region), name_style = "none")]
+fn handle_payment(company_id: u64, region: &str) {
+    charge_card();
+}
+
+#[test]
+fn test_inherited_context_as_key_value_pairs() {
+    let lines = capture_logfmt(LogfmtFormat::new().with_timestamp(false), || {
+        handle_payment(42, "eu west")
+    });
+
+    assert_eq!(
+        lines,
+        vec![
+            "level=info target=logfmt_tests msg=\"Charging card\" amount=100 \
+             note=\"first try\" company_id=42 region=\"\\\"eu west\\\"\""
+        ]
+    );
+}
+
+#[test]
+fn test_function_name_follows_message() {
+    let lines = capture_logfmt(LogfmtFormat::new().with_timestamp(false), || {
+        tracing::info!(attempt = 2, function = "retry_payment", "Retrying");
+    });
+
+    assert_eq!(
+        lines,
+        vec!["level=info target=logfmt_tests msg=Retrying function=retry_payment attempt=2"]
+    );
+}
+
+#[params(fields(user_id), name_style = "none")]
+fn quoted_values(user_id: String) {
+    info!(path = "C:\\tmp", "Line one\nline \"two\"");
+}
+
+#[test]
+fn test_values_are_quoted_and_escaped() {
+    let lines = capture_logfmt(
+        LogfmtFormat::new().with_timestamp(false).with_target(false),
+        || quoted_values("a=b".to_string()),
+    );
+
+    assert_eq!(
+        lines,
+        vec![
+            r#"level=info msg="Line one\nline \"two\"" user_id="\"a=b\"" path="C:\\tmp""#
+        ]
+    );
+}
+
+#[test]
+fn test_timestamp_comes_first() {
+    let lines = capture_logfmt(LogfmtFormat::new(), || {
+        tracing::warn!("No context here");
+    });
+
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].starts_with("ts="));
+    assert!(lines[0].ends_with(" level=warn target=logfmt_tests msg=\"No context here\""));
+}
@@ -0,0 +1,197 @@
+//! Tests that fully-qualified and aliased log macros inside `#[params]` bodies
+//! carry the configured fields
+
+mod common;
+
+use common::capture_logs;
+use log_args::params;
+
+#[params(fields(order_id))]
+fn qualified_macros(order_id: u64) {
+    tracing::info!("Qualified info");
+    ::tracing::warn!(target: "orders", attempt = 2, "Qualified warn with target");
+    tracing::event!(tracing::Level::ERROR, "Explicit level event");
+}
+
+#[test]
+fn test_qualified_tracing_macros_carry_fields() {
+    let logs = capture_logs(|| qualified_macros(7));
+    assert_eq!(logs.len(), 3);
+
+    assert_eq!(logs[0]["fields"]["message"].as_str(), Some("Qualified info"));
+    assert_eq!(logs[0]["fields"]["order_id"].as_str(), Some("7"));
+
+    assert_eq!(logs[1]["level"].as_str(), Some("WARN"));
+    assert_eq!(logs[1]["target"].as_str(), Some("orders"));
+    assert_eq!(logs[1]["fields"]["attempt"].as_i64(), Some(2));
+    assert_eq!(logs[1]["fields"]["order_id"].as_str(), Some("7"));
+
+    assert_eq!(logs[2]["level"].as_str(), Some("ERROR"));
+    assert_eq!(
+        logs[2]["fields"]["message"].as_str(),
+        Some("Explicit level event")
+    );
+    assert_eq!(logs[2]["fields"]["order_id"].as_str(), Some("7"));
+}
+
+#[params(fields(order_id))]
+async fn place_order(order_id: u64) {
+    ship_order().await;
+}
+
+#[params]
+async fn ship_order() {
+    tracing::info!("Shipping");
+    tracing::event!(tracing::Level::WARN, "Shipping late");
+}
+
+#[test]
+fn test_qualified_macros_inherit_async_context() {
+    let logs = capture_logs(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(place_order(9));
+    });
+    assert_eq!(logs.len(), 2);
+    for log in &logs {
+        assert_eq!(log["fields"]["order_id"].as_str(), Some("9"));
+    }
+}
+
+#[params(fields(order_id))]
+fn aliased_macros(order_id: u64) {
+    use tracing::{event, info as log_info, Level};
+
+    log_info!("Aliased info");
+    event!(Level::DEBUG, "Bare event");
+}
+
+#[test]
+fn test_aliased_macros_carry_fields() {
+    let logs = capture_logs(|| aliased_macros(11));
+    assert_eq!(logs.len(), 2);
+
+    assert_eq!(logs[0]["fields"]["message"].as_str(), Some("Aliased info"));
+    assert_eq!(logs[0]["fields"]["order_id"].as_str(), Some("11"));
+    assert_eq!(logs[1]["level"].as_str(), Some("DEBUG"));
+    assert_eq!(logs[1]["fields"]["order_id"].as_str(), Some("11"));
+}
+
+#[params(fields(order_id))]
+fn own_event_macro(order_id: u64) -> u64 {
+    macro_rules! event {
+        ($value:expr) => {
+            $value * 2
+        };
+    }
+
+    event!(order_id)
+}
+
+#[test]
+fn test_bare_event_not_imported_from_tracing_is_left_alone() {
+    let mut doubled = 0;
+    let logs = capture_logs(|| doubled = own_event_macro(7));
+    assert_eq!(doubled, 14);
+    assert!(logs.is_empty());
+}
+
+#[params(fields(order_id))]
+fn log_crate_macros(order_id: u64) {
+    let status = "shipped";
+    log::info!("Log crate info {}", status);
+    log::warn!(carrier = "ups", status:? = status; "Log crate key-values");
+}
+
+#[test]
+fn test_log_crate_macros_carry_fields() {
+    let logs = capture_logs(|| log_crate_macros(3));
+    assert_eq!(logs.len(), 2);
+
+    assert_eq!(
+        logs[0]["fields"]["message"].as_str(),
+        Some("Log crate info shipped")
+    );
+    assert_eq!(logs[0]["fields"]["order_id"].as_str(), Some("3"));
+
+    assert_eq!(logs[1]["level"].as_str(), Some("WARN"));
+    assert_eq!(logs[1]["fields"]["carrier"].as_str(), Some("ups"));
+    assert_eq!(logs[1]["fields"]["status"].as_str(), Some("\"shipped\""));
+    assert_eq!(logs[1]["fields"]["order_id"].as_str(), Some("3"));
+}
+
+// User macros wrapping tracing, as a service might define them
+macro_rules! audit {
+    ($($t:tt)*) => {
+        tracing::info!(audit = true, $($t)*)
+    };
+}
+
+macro_rules! metric {
+    ($($t:tt)*) => {
+        tracing::event!(tracing::Level::DEBUG, $($t)*)
+    };
+}
+
+#[params(fields(order_id), custom(service = "orders"), macros(audit, metric))]
+fn custom_macros(order_id: u64) {
+    audit!("Order approved");
+    metric!(latency_ms = 12, "Approval latency");
+}
+
+#[test]
+fn test_listed_user_macros_receive_fields() {
+    let logs = capture_logs(|| custom_macros(5));
+    assert_eq!(logs.len(), 2);
+
+    assert_eq!(logs[0]["fields"]["message"].as_str(), Some("Order approved"));
+    assert_eq!(logs[0]["fields"]["audit"].as_bool(), Some(true));
+    assert_eq!(logs[0]["fields"]["order_id"].as_str(), Some("5"));
+    assert_eq!(logs[0]["fields"]["service"].as_str(), Some("orders"));
+
+    assert_eq!(logs[1]["level"].as_str(), Some("DEBUG"));
+    assert_eq!(logs[1]["fields"]["latency_ms"].as_i64(), Some(12));
+    assert_eq!(logs[1]["fields"]["order_id"].as_str(), Some("5"));
+}
+
+// Logs two events and returns how many it logged
+macro_rules! audit_twice {
+    ($($t:tt)*) => {{
+        tracing::info!(stage = "before", $($t)*);
+        tracing::info!(stage = "after", $($t)*);
+        2
+    }};
+}
+
+#[params(fields(order_id), macros(audit_twice))]
+fn multi_event_macro(order_id: u64) -> u32 {
+    audit_twice!("Order audited")
+}
+
+#[test]
+fn test_listed_user_macros_may_log_several_events_and_return_a_value() {
+    let mut logged = 0;
+    let logs = capture_logs(|| logged = multi_event_macro(8));
+    assert_eq!(logged, 2);
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[0]["fields"]["stage"].as_str(), Some("before"));
+    assert_eq!(logs[1]["fields"]["stage"].as_str(), Some("after"));
+    for log in &logs {
+        assert_eq!(log["fields"]["message"].as_str(), Some("Order audited"));
+        assert_eq!(log["fields"]["order_id"].as_str(), Some("8"));
+    }
+}
+
+#[params(fields(order_id))]
+fn unlisted_user_macro(order_id: u64) {
+    audit!("Not listed");
+}
+
+#[test]
+fn test_unlisted_user_macros_are_untouched() {
+    let logs = capture_logs(|| unlisted_user_macro(5));
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0]["fields"]["audit"].as_bool(), Some(true));
+    assert!(logs[0]["fields"]["order_id"].is_null());
+}
@@ -0,0 +1,89 @@
+//! Tests for the `name_style` attribute and the casing of the `function` field
+
+mod common;
+
+use common::capture_logs;
+use log_args::params;
+
+fn function_field<F: FnOnce()>(f: F) -> Option<String> {
+    let logs = capture_logs(f);
+    assert_eq!(logs.len(), 1);
+    logs[0]["fields"]["function"].as_str().map(str::to_string)
+}
+
+#[params(name_style = "snake")]
+fn process_payment() {
+    info!("Snake");
+}
+
+#[params(name_style = "camel")]
+fn get_v2_user() {
+    info!("Camel");
+}
+
+#[params(name_style = "pascal")]
+fn _internal_sync() {
+    info!("Pascal");
+}
+
+#[params(name_style = "screaming")]
+fn r#type() {
+    info!("Screaming");
+}
+
+#[allow(non_snake_case)]
+#[params(name_style = "kebab")]
+fn parseHTTPRequest() {
+    info!("Kebab");
+}
+
+#[allow(non_snake_case)]
+#[params(name_style = "snake")]
+fn utf8ToHTTP2Frame() {
+    info!("Snake from camel");
+}
+
+#[test]
+fn test_name_style_attribute_selects_casing() {
+    assert_eq!(
+        function_field(process_payment).as_deref(),
+        Some("process_payment")
+    );
+    assert_eq!(function_field(get_v2_user).as_deref(), Some("getV2User"));
+    assert_eq!(
+        function_field(_internal_sync).as_deref(),
+        Some("_InternalSync")
+    );
+    assert_eq!(function_field(r#type).as_deref(), Some("TYPE"));
+    assert_eq!(
+        function_field(parseHTTPRequest).as_deref(),
+        Some("parse-http-request")
+    );
+    assert_eq!(
+        function_field(utf8ToHTTP2Frame).as_deref(),
+        Some("utf8_to_http2_frame")
+    );
+}
+
+#[params(name_style = "none")]
+fn unnamed() {
+    info!("No function field");
+}
+
+#[test]
+fn test_name_style_none_disables_function_field() {
+    assert_eq!(function_field(unnamed), None);
+}
+
+#[params(name_style = "kebab", name = "billing.charge")]
+fn overridden() {
+    info!("Override");
+}
+
+#[test]
+fn test_name_override_is_not_cased() {
+    assert_eq!(
+        function_field(overridden).as_deref(),
+        Some("billing.charge")
+    );
+}
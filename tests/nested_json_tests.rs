@@ -0,0 +1,121 @@
+//! Tests for expanding dotted field keys into nested JSON objects
+
+mod common;
+
+use log_args::params;
+use log_args_runtime::{DottedKeyConflict, LogArgsLayer};
+use serde_json::Value;
+use tracing_subscriber::{prelude::*, Registry};
+
+fn capture_nested<F: FnOnce()>(conflict: DottedKeyConflict, f: F) -> Vec<Value> {
+    let output = common::capture_output(
+        |writer| {
+            Registry::default().with(
+                LogArgsLayer::new()
+                    .flatten_event(true)
+                    .nest_dotted_keys(conflict)
+                    .with_writer(move || writer.clone()),
+            )
+        },
+        f,
+    );
+    common::parse_json_lines(&output)
+}
+
+struct Contact {
+    email: String,
+}
+
+struct Person {
+    id: u64,
+    contact: Contact,
+}
+
+fn send_receipt() {
+    tracing::info!(receipt.id = 5, "Sending receipt");
+}
+
+#[params(fields(person.id, person.contact.email))]
+fn notify(person: Person) {
+    info!("Notifying");
+    send_receipt();
+}
+
+fn person() -> Person {
+    Person {
+        id: 7,
+        contact: Contact {
+            email: "a@example.com".to_string(),
+        },
+    }
+}
+
+#[test]
+fn test_dotted_keys_become_nested_objects() {
+    let logs = capture_nested(DottedKeyConflict::default(), || notify(person()));
+    assert_eq!(logs.len(), 2);
+
+    for log in &logs {
+        assert_eq!(log["person"]["id"].as_str(), Some("7"));
+        assert_eq!(
+            log["person"]["contact"]["email"].as_str(),
+            Some("\"a@example.com\"")
+        );
+        assert!(log["person.contact.email"].is_null());
+    }
+    assert_eq!(logs[1]["receipt"]["id"].as_i64(), Some(5));
+}
+
+#[test]
+fn test_flat_keys_are_kept_without_option() {
+    let output = common::capture_output(
+        |writer| {
+            Registry::default().with(
+                LogArgsLayer::new()
+                    .flatten_event(true)
+                    .with_writer(move || writer.clone()),
+            )
+        },
+        || notify(person()),
+    );
+
+    let logs = common::parse_json_lines(&output);
+    assert_eq!(logs[0]["person.id"].as_str(), Some("7"));
+    assert!(logs[0]["person"].is_null());
+}
+
+fn log_conflict() {
+    tracing::info!(
+        user = "alice",
+        user.id = 7,
+        user.id.kind = "internal",
+        "Conflict"
+    );
+}
+
+#[test]
+fn test_conflict_moves_scalar_to_value() {
+    let logs = capture_nested(DottedKeyConflict::MoveScalarToValue, log_conflict);
+
+    assert_eq!(logs[0]["user"]["value"].as_str(), Some("alice"));
+    assert_eq!(logs[0]["user"]["id"]["value"].as_i64(), Some(7));
+    assert_eq!(logs[0]["user"]["id"]["kind"].as_str(), Some("internal"));
+}
+
+#[test]
+fn test_conflict_keeps_dotted_key() {
+    let logs = capture_nested(DottedKeyConflict::KeepDotted, log_conflict);
+
+    assert_eq!(logs[0]["user"].as_str(), Some("alice"));
+    assert_eq!(logs[0]["user.id"].as_i64(), Some(7));
+    assert_eq!(logs[0]["user.id.kind"].as_str(), Some("internal"));
+}
+
+#[test]
+fn test_conflict_underscores_dotted_key() {
+    let logs = capture_nested(DottedKeyConflict::Underscore, log_conflict);
+
+    assert_eq!(logs[0]["user"].as_str(), Some("alice"));
+    assert_eq!(logs[0]["user_id"].as_i64(), Some(7));
+    assert_eq!(logs[0]["user_id_kind"].as_str(), Some("internal"));
+}
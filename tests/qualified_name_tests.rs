@@ -0,0 +1,133 @@
+//! Tests for `name = "..."`, `qualified` and impl-level `#[params]`, which control the
+//! `function` field
+//!
+//! `name_style` is pinned so the names don't depend on the `function-names` feature.
+
+mod common;
+
+use common::capture_logs;
+use log_args::params;
+
+#[params(name = "billing.charge")]
+fn charge() {
+    info!("Charging");
+}
+
+#[test]
+fn test_name_override() {
+    let logs = capture_logs(charge);
+    assert_eq!(logs.len(), 1);
+    assert_eq!(
+        logs[0]["fields"]["function"].as_str(),
+        Some("billing.charge")
+    );
+}
+
+#[params(name = "billing.outer")]
+fn outer() {
+    inner();
+}
+
+#[params(name_style = "none")]
+fn inner() {
+    info!("Inner");
+}
+
+#[test]
+fn test_name_override_is_inherited() {
+    let logs = capture_logs(outer);
+    assert_eq!(logs.len(), 1);
+    assert_eq!(
+        logs[0]["fields"]["function"].as_str(),
+        Some("billing.outer")
+    );
+}
+
+struct UserService;
+struct AdminService;
+
+impl UserService {
+    #[params(qualified, name_style = "snake")]
+    fn process(&self) {
+        info!("Processing");
+    }
+}
+
+impl AdminService {
+    #[params(qualified, name_style = "snake")]
+    fn process(&self) {
+        info!("Processing");
+    }
+}
+
+#[test]
+fn test_qualified_method_names_differ_by_type() {
+    let logs = capture_logs(|| {
+        UserService.process();
+        AdminService.process();
+    });
+    assert_eq!(logs.len(), 2);
+    assert_eq!(
+        logs[0]["fields"]["function"].as_str(),
+        Some("UserService::process")
+    );
+    assert_eq!(
+        logs[1]["fields"]["function"].as_str(),
+        Some("AdminService::process")
+    );
+}
+
+trait Handler {
+    fn handle(&self, id: u64);
+}
+
+struct OrderService<T> {
+    _marker: std::marker::PhantomData<T>,
+}
+
+#[params(qualified, name_style = "snake")]
+impl<T> OrderService<T> {
+    fn new() -> Self {
+        info!("Creating service");
+        Self {
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    #[params(fields(id))]
+    fn process_user(&self, id: u64) {
+        info!("Processing user");
+    }
+}
+
+#[params(qualified, name_style = "snake")]
+impl<T> Handler for OrderService<T> {
+    fn handle(&self, _id: u64) {
+        info!("Handling");
+    }
+}
+
+#[test]
+fn test_impl_level_attribute_qualifies_every_method() {
+    let logs = capture_logs(|| {
+        let service = OrderService::<String>::new();
+        service.process_user(5);
+        service.handle(6);
+    });
+    assert_eq!(logs.len(), 3);
+
+    assert_eq!(
+        logs[0]["fields"]["function"].as_str(),
+        Some("OrderService::new")
+    );
+    assert_eq!(
+        logs[1]["fields"]["function"].as_str(),
+        Some("OrderService::process_user")
+    );
+    // Method-level attributes add to the impl-level ones
+    assert_eq!(logs[1]["fields"]["id"].as_str(), Some("5"));
+    assert_eq!(
+        logs[2]["fields"]["function"].as_str(),
+        Some("<OrderService as Handler>::handle")
+    );
+}
@@ -0,0 +1,113 @@
+//! Tests for the request id helpers and `request_id = expr`
+
+mod common;
+
+use common::capture_logs;
+use log_args::params;
+use log_args_runtime::{request_id_from_headers, RequestIdFormat};
+use std::collections::HashMap;
+
+#[test]
+fn test_generated_formats() {
+    let v4 = RequestIdFormat::UuidV4.generate();
+    assert_eq!(v4.len(), 36);
+    assert_eq!(&v4[14..15], "4");
+
+    let v7 = RequestIdFormat::UuidV7.generate();
+    assert_eq!(v7.len(), 36);
+    assert_eq!(&v7[14..15], "7");
+
+    let ulid = RequestIdFormat::Ulid.generate();
+    assert_eq!(ulid.len(), 26);
+    assert!(ulid
+        .chars()
+        .all(|ch| ch.is_ascii_digit() || ch.is_ascii_uppercase()));
+
+    assert_ne!(RequestIdFormat::UuidV4.generate(), v4);
+}
+
+#[test]
+fn test_request_id_from_headers() {
+    let headers = [
+        ("Content-Type", "text/plain"),
+        ("X-Request-Id", " abc-123 "),
+    ];
+    assert_eq!(request_id_from_headers(headers).as_deref(), Some("abc-123"));
+
+    // Earlier names in the list win, whatever the header order
+    let headers = [("x-correlation-id", "corr-1"), ("x-request-id", "req-1")];
+    assert_eq!(request_id_from_headers(headers).as_deref(), Some("req-1"));
+
+    // Unusable values fall through to the next header
+    let headers = [("x-request-id", "evil\nline"), ("request-id", "req-2")];
+    assert_eq!(request_id_from_headers(headers).as_deref(), Some("req-2"));
+    let long = "a".repeat(129);
+    assert_eq!(
+        request_id_from_headers([("x-request-id", long.as_str())]),
+        None
+    );
+    assert_eq!(request_id_from_headers([("x-request-id", "")]), None);
+}
+
+#[params(root, request_id = request_id_from_headers(&headers))]
+fn handle_request(headers: HashMap<String, String>) {
+    info!("Handling request");
+    load_user();
+}
+
+fn load_user() {
+    log_args_runtime::info!("Loading user");
+}
+
+#[test]
+fn test_request_id_from_expression_reaches_every_log() {
+    let mut headers = HashMap::new();
+    headers.insert("X-Request-Id".to_string(), "req-from-client".to_string());
+
+    let logs = capture_logs(|| handle_request(headers));
+    assert_eq!(logs.len(), 2);
+    for log in &logs {
+        assert_eq!(
+            log["fields"]["request_id"].as_str(),
+            Some("req-from-client")
+        );
+    }
+}
+
+#[test]
+fn test_missing_request_id_is_generated() {
+    let logs = capture_logs(|| handle_request(HashMap::new()));
+    let request_id = logs[0]["fields"]["request_id"].as_str().unwrap();
+    assert!(!request_id.is_empty());
+    assert_eq!(logs[1]["fields"]["request_id"].as_str(), Some(request_id));
+}
+
+#[params(request_id = job_id)]
+fn run_job(job_id: &str) {
+    info!("Running job");
+}
+
+#[test]
+fn test_request_id_without_root() {
+    let logs = capture_logs(|| run_job("job-9"));
+    assert_eq!(logs[0]["fields"]["request_id"].as_str(), Some("job-9"));
+}
+
+#[params(root)]
+fn generated_root(request_id: &mut String) {
+    *request_id = log_args_runtime::get_context_value("request_id").unwrap();
+}
+
+#[test]
+fn test_configured_format_is_used_by_root() {
+    log_args_runtime::set_request_id_format(RequestIdFormat::Ulid);
+    let mut request_id = String::new();
+    generated_root(&mut request_id);
+    log_args_runtime::set_request_id_format(RequestIdFormat::UuidV4);
+
+    assert_eq!(request_id.len(), 26);
+    assert_eq!(
+        log_args_runtime::request_id_format(),
+        RequestIdFormat::UuidV4
+    );
+}
@@ -0,0 +1,102 @@
+//! Tests for `root` and `isolate`, which keep a function from inheriting context left
+//! behind by earlier requests or by its caller
+
+mod common;
+
+use common::capture_logs;
+use log_args::params;
+use std::collections::HashMap;
+
+fn leak_context(key: &str, value: &str) {
+    let mut context = HashMap::new();
+    context.insert(key.to_string(), value.to_string());
+    std::mem::forget(log_args_runtime::push_context(context));
+}
+
+#[params(root, fields(order_id))]
+fn handle_order(order_id: u64) {
+    info!("Handling order");
+    log_args_runtime::info!("Child of the root");
+}
+
+#[params(root)]
+fn inspect_root(seen: &mut HashMap<String, String>) {
+    *seen = log_args_runtime::get_merged_context();
+}
+
+#[test]
+fn test_root_ignores_leftover_context() {
+    leak_context("stale_user", "previous request");
+    log_args_runtime::set_global_context("stale_global", "previous request");
+
+    let logs = capture_logs(|| handle_order(7));
+    assert_eq!(logs.len(), 2);
+    for log in &logs {
+        assert!(log["fields"]["stale_user"].is_null());
+        assert!(!log["fields"]["order_id"].is_null());
+    }
+
+    leak_context("stale_user", "previous request");
+    let mut seen = HashMap::new();
+    inspect_root(&mut seen);
+    assert!(!seen.contains_key("stale_user"));
+    assert!(!seen.contains_key("stale_global"));
+    assert!(seen.contains_key("request_id"));
+
+    // The leftover frames are gone for good; the global store is visible again
+    assert!(log_args_runtime::get_context_value("stale_user").is_none());
+    assert_eq!(
+        log_args_runtime::get_context_value("stale_global").as_deref(),
+        Some("previous request")
+    );
+}
+
+#[test]
+fn test_root_generates_a_request_id() {
+    let logs = capture_logs(|| handle_order(7));
+    let request_id = logs[0]["fields"]["request_id"].as_str().unwrap();
+    assert_eq!(request_id.len(), 36);
+    assert_eq!(logs[1]["fields"]["request_id"].as_str(), Some(request_id));
+
+    let other = capture_logs(|| handle_order(8));
+    assert_ne!(other[0]["fields"]["request_id"].as_str(), Some(request_id));
+}
+
+#[params(root, custom(request_id = "req-42"))]
+fn handle_known_request() {
+    info!("Handling known request");
+}
+
+#[test]
+fn test_root_keeps_a_logged_request_id() {
+    let logs = capture_logs(handle_known_request);
+    assert_eq!(logs[0]["fields"]["request_id"].as_str(), Some("req-42"));
+}
+
+#[params(isolate, custom(job = "cleanup"))]
+fn cleanup_job(seen_tenant: &mut Option<String>) {
+    info!("Cleaning up");
+    *seen_tenant = log_args_runtime::get_context_value("tenant");
+}
+
+#[params(custom(tenant = "acme"))]
+fn handle_upload(seen_tenant: &mut Option<String>) {
+    cleanup_job(seen_tenant);
+    info!("Upload done");
+}
+
+#[test]
+fn test_isolate_hides_and_restores_the_parent_context() {
+    let mut seen_tenant = None;
+    let logs = capture_logs(|| handle_upload(&mut seen_tenant));
+    assert_eq!(logs.len(), 2);
+
+    assert!(seen_tenant.is_none());
+    assert!(logs[0]["fields"]["tenant"].is_null());
+    assert_eq!(logs[0]["fields"]["job"].as_str(), Some("cleanup"));
+    assert!(logs[0]["fields"]["request_id"].is_null());
+
+    // Back in the parent, its context is in place again
+    assert_eq!(logs[1]["fields"]["tenant"].as_str(), Some("acme"));
+    assert!(logs[1]["fields"]["job"].is_null());
+}
@@ -0,0 +1,108 @@
+//! Tests for the RFC 5424 syslog output layer
+
+mod common;
+
+use log_args::params;
+use log_args_runtime::{SyslogFraming, SyslogLayer};
+use std::io::Read;
+use std::net::TcpListener;
+use std::sync::Mutex;
+use tracing_subscriber::{prelude::*, Registry};
+
+fn syslog_layer() -> SyslogLayer {
+    SyslogLayer::new()
+        .with_hostname("api-1")
+        .with_app_name("billing")
+}
+
+fn capture_syslog<F: FnOnce()>(layer: SyslogLayer, f: F) -> Vec<String> {
+    let output = common::capture_output(
+        |writer| Registry::default().with(layer.with_writer(move || writer.clone())),
+        f,
+    );
+    output.lines().map(str::to_string).collect()
+}
+
+// Drop the timestamp and process id, which change between runs
+fn without_timestamp_and_pid(line: &str) -> String {
+    let parts: Vec<&str> = line.splitn(6, ' ').collect();
+    format!("{} {} {} {}", parts[0], parts[2], parts[3], parts[5])
+}
+
+fn charge_card() {
+    tracing::info!(amount = 100, "Charging card");
+}
+
+#[params(fields(company_id, region), name_style = "none")]
+fn handle_payment(company_id: u64, region: &str) {
+    charge_card();
+}
+
+#[test]
+fn test_context_in_structured_data_element() {
+    let lines = capture_syslog(syslog_layer(), || handle_payment(42, "eu"));
+    assert_eq!(lines.len(), 1);
+
+    assert_eq!(
+        without_timestamp_and_pid(&lines[0]),
+        "<14>1 api-1 billing - [logargs@32473 amount=\"100\" company_id=\"42\" \
+         region=\"\\\"eu\\\"\"] Charging card"
+    );
+    assert!(lines[0].contains(&format!(" {} - ", std::process::id())));
+}
+
+#[test]
+fn test_function_first_and_values_escaped() {
+    let layer = syslog_layer().with_facility(16).with_sd_id("acme@12345");
+    let lines = capture_syslog(layer, || {
+        tracing::error!(note = "a]b\\c", function = "retry_payment", "Retry failed")
+    });
+    assert_eq!(lines.len(), 1);
+
+    assert_eq!(
+        without_timestamp_and_pid(&lines[0]),
+        "<131>1 api-1 billing - [acme@12345 function=\"retry_payment\" note=\"a\\]b\\\\c\"] Retry failed"
+    );
+}
+
+#[test]
+fn test_without_fields_uses_nil_structured_data() {
+    let lines = capture_syslog(syslog_layer(), || tracing::warn!("Plain"));
+    assert_eq!(
+        lines
+            .iter()
+            .map(|line| without_timestamp_and_pid(line))
+            .collect::<Vec<_>>(),
+        vec!["<12>1 api-1 billing - - Plain"]
+    );
+}
+
+#[test]
+fn test_octet_counting_over_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let stream = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut accepted, _) = listener.accept().unwrap();
+
+    let layer = syslog_layer()
+        .with_framing(SyslogFraming::OctetCounting)
+        .with_writer(Mutex::new(stream));
+    tracing::subscriber::with_default(Registry::default().with(layer), || {
+        tracing::info!("First");
+        tracing::info!("Second message");
+    });
+
+    let mut received = String::new();
+    accepted.read_to_string(&mut received).unwrap();
+
+    let mut messages = Vec::new();
+    let mut rest = received.as_str();
+    while !rest.is_empty() {
+        let (len, tail) = rest.split_once(' ').unwrap();
+        let len: usize = len.parse().unwrap();
+        messages.push(&tail[..len]);
+        rest = &tail[len..];
+    }
+    assert_eq!(messages.len(), 2);
+    assert!(messages[0].ends_with("- First"));
+    assert!(messages[1].ends_with("- Second message"));
+}
@@ -0,0 +1,16 @@
+use log_args::params;
+
+#[params(root)]
+async fn handle_request() {
+    info!("Handling request");
+}
+
+#[params(isolate)]
+async fn cleanup_job() {
+    info!("Cleaning up");
+}
+
+fn main() {
+    let _ = handle_request();
+    let _ = cleanup_job();
+}
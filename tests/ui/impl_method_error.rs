@@ -0,0 +1,29 @@
+use log_args::params;
+
+struct Counter {
+    count: u32,
+}
+
+#[params]
+impl Counter {
+    fn get(&self) -> u32 {
+        self.count
+    }
+
+    #[params(not_an_attribute)]
+    fn increment(&mut self) {
+        self.count += 1;
+    }
+
+    #[params(also_not_an_attribute)]
+    fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+
+fn main() {
+    let mut counter = Counter { count: 0 };
+    counter.increment();
+    assert_eq!(counter.get(), 1);
+    counter.reset();
+}
@@ -0,0 +1,6 @@
+use log_args::params;
+
+#[params(fields(user_id), prefix = "app")]
+fn load_orders(user_id: u64) {}
+
+fn main() {}
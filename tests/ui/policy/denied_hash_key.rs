@@ -0,0 +1,11 @@
+use log_args::params;
+
+struct User {
+    id: u64,
+    email: String,
+}
+
+#[params(fields(user.id), hash(user.email))]
+fn update_user(user: User) {}
+
+fn main() {}
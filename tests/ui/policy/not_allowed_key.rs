@@ -0,0 +1,6 @@
+use log_args::params;
+
+#[params(fields(request_id), custom(tenant = "acme"))]
+fn handle(request_id: String) {}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use log_args::params;
+
+#[params(all)]
+fn login(user: String, password: String) {}
+
+#[params(all, strict)]
+fn change_password(user: String, password: String) {}
+
+fn main() {}
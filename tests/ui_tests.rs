@@ -0,0 +1,25 @@
+//! Compile-fail tests for the errors `#[params]` reports
+
+use std::path::Path;
+
+/// Policy for the cases in `tests/ui/policy`. trybuild builds every case in one project,
+/// so the others must stay within it too; `password` is allowed for the sensitive-parameter
+/// cases.
+const POLICY: &str = r#"
+[fields]
+allow = ["user", "password", "request_id", "app"]
+deny = ["user.email", "app.user_id"]
+"#;
+
+#[test]
+fn ui() {
+    // trybuild compiles the cases in its own project, so the policy goes next to that manifest
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).parent().unwrap();
+    let project_dir = target_dir.join("tests").join("trybuild").join("log_args");
+    std::fs::create_dir_all(&project_dir).unwrap();
+    std::fs::write(project_dir.join("log_args.toml"), POLICY).unwrap();
+
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+    cases.compile_fail("tests/ui/policy/*.rs");
+}